@@ -0,0 +1,482 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use mavlink::ardupilotmega::MavMessage;
+use mavlink::Message;
+
+use crate::filter::{Expr, Literal, Op, Operand, Subject, Value};
+
+/// A filter expression that raises an alert for every live entry it matches,
+/// e.g. `HEARTBEAT and sys == 1 and age > 3`.
+pub struct AlertRule {
+    pub text: String,
+    expr: Expr,
+    /// Compares `hz`, which needs a full rate window before it means anything
+    uses_rate: bool,
+    /// The message the rule names, checked while it has not been received
+    absent: Option<Absent>,
+}
+
+impl AlertRule {
+    pub fn parse(text: &str) -> Result<Self> {
+        let expr = Expr::parse(text)
+            .map_err(|e| anyhow::anyhow!("Invalid alert rule '{}': {}", text, e))?
+            .with_context(|| "Empty alert rule".to_string())?;
+        Ok(Self {
+            text: text.trim().to_string(),
+            uses_rate: expr.uses(&|operand| matches!(operand, Operand::Hz)),
+            absent: Absent::of(&expr),
+            expr,
+        })
+    }
+}
+
+/// Reads one rule per line; blank lines and lines starting with `#` are skipped.
+pub fn load_rules(path: &str) -> Result<Vec<AlertRule>> {
+    let text = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| AlertRule::parse(line).with_context(|| format!("{} line {}", path, i + 1)))
+        .collect()
+}
+
+/// What rules are evaluated against: a table entry, or while the view is
+/// paused, the entry with the queued messages applied.
+pub trait Monitored: Subject {
+    fn first_received(&self) -> Instant;
+}
+
+#[derive(Clone)]
+pub struct AlertEvent {
+    pub time: DateTime<Local>,
+    pub rule: String,
+    pub sys_id: u8,
+    pub comp_id: u8,
+    pub message: String,
+    /// Rule started matching; otherwise it stopped
+    pub triggered: bool,
+}
+
+impl AlertEvent {
+    pub fn describe(&self) -> String {
+        format!(
+            "alert {}: {} on {} {}:{}",
+            if self.triggered {
+                "triggered"
+            } else {
+                "cleared"
+            },
+            self.rule,
+            self.message,
+            self.sys_id,
+            self.comp_id
+        )
+    }
+}
+
+/// Seconds a rule has to keep matching, or keep not matching, before it
+/// triggers or clears, so a value hovering at a threshold does not flap
+pub const DEFAULT_HOLD_SECS: f64 = 1.0;
+/// Alert log entries kept, oldest dropped first
+const LOG_LEN: usize = 1000;
+
+/// What a rule is evaluated against: a table entry by index, or the entry a
+/// rule names when it has not been received (see `Absent`).
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Target {
+    Entry(usize),
+    Absent,
+}
+
+/// Confirmed state of one rule on one target.
+struct Edge {
+    active: bool,
+    /// When the rule started disagreeing with `active`
+    differs_since: Option<Instant>,
+}
+
+pub struct Alerts {
+    pub rules: Vec<AlertRule>,
+    edges: HashMap<(usize, Target), Edge>,
+    pub log: VecDeque<AlertEvent>,
+    /// Events not yet taken by the main loop for the bell and the recording
+    new: Vec<AlertEvent>,
+    /// Ring the terminal bell when a rule triggers
+    pub bell: bool,
+    pub hold_secs: f64,
+    /// First evaluation, which rate rules on absent entries wait a window from
+    started: Option<Instant>,
+}
+
+impl Default for Alerts {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            edges: HashMap::new(),
+            log: VecDeque::new(),
+            new: Vec::new(),
+            bell: false,
+            hold_secs: DEFAULT_HOLD_SECS,
+            started: None,
+        }
+    }
+}
+
+impl Alerts {
+    /// Re-evaluates every rule against every entry, and against the entry a
+    /// rule names if it was never received, logging the rules that have
+    /// started or stopped matching for at least `hold_secs`.
+    pub fn evaluate<E: Monitored>(&mut self, entries: &[E], now: Instant, hz_window_secs: f64) {
+        let started = *self.started.get_or_insert(now);
+        let mut changes = Vec::new();
+        for (r, rule) in self.rules.iter_mut().enumerate() {
+            for (e, entry) in entries.iter().enumerate() {
+                let warming_up = rule.uses_rate
+                    && now.duration_since(entry.first_received()).as_secs_f64() < hz_window_secs;
+                let matched = !warming_up && rule.expr.matches(entry);
+                changes.push(((r, Target::Entry(e)), matched, describe(entry)));
+            }
+            if let Some(absent) = &mut rule.absent {
+                absent.age = now.duration_since(started).as_secs_f64();
+                let present = entries.iter().any(|entry| absent.is(entry));
+                let warming_up =
+                    rule.uses_rate && now.duration_since(started).as_secs_f64() < hz_window_secs;
+                let matched = !present && !warming_up && rule.expr.matches(absent);
+                changes.push(((r, Target::Absent), matched, describe(absent)));
+            }
+        }
+        for (key, matched, (sys_id, comp_id, message)) in changes {
+            if self.settle(key, matched, now) {
+                self.push(AlertEvent {
+                    time: Local::now(),
+                    rule: self.rules[key.0].text.clone(),
+                    sys_id,
+                    comp_id,
+                    message,
+                    triggered: matched,
+                });
+            }
+        }
+    }
+
+    /// Feeds the latest match result of a rule on a target; true when it
+    /// flips the confirmed state.
+    fn settle(&mut self, key: (usize, Target), matched: bool, now: Instant) -> bool {
+        let edge = self.edges.entry(key).or_insert(Edge {
+            active: false,
+            differs_since: None,
+        });
+        if matched == edge.active {
+            edge.differs_since = None;
+            return false;
+        }
+        let since = *edge.differs_since.get_or_insert(now);
+        if now.duration_since(since).as_secs_f64() < self.hold_secs {
+            return false;
+        }
+        edge.active = matched;
+        edge.differs_since = None;
+        true
+    }
+
+    fn push(&mut self, event: AlertEvent) {
+        if self.log.len() == LOG_LEN {
+            self.log.pop_front();
+        }
+        self.log.push_back(event.clone());
+        self.new.push(event);
+    }
+
+    pub fn is_active(&self, entry: usize) -> bool {
+        self.edges
+            .iter()
+            .any(|(&(_, target), edge)| edge.active && target == Target::Entry(entry))
+    }
+
+    pub fn active_count(&self) -> usize {
+        self.edges.values().filter(|edge| edge.active).count()
+    }
+
+    pub fn take_new(&mut self) -> Vec<AlertEvent> {
+        std::mem::take(&mut self.new)
+    }
+}
+
+fn describe<S: Subject + ?Sized>(subject: &S) -> (u8, u8, String) {
+    (
+        subject.system_id(),
+        subject.component_id(),
+        subject.name().to_string(),
+    )
+}
+
+/// The message a rule names, standing in for it until it is first received:
+/// never seen means an `age` counted from when the rules were first evaluated,
+/// a `hz` and `count` of zero and no fields.
+struct Absent {
+    name: String,
+    message_id: u32,
+    sys_id: Option<u8>,
+    comp_id: Option<u8>,
+    /// Seconds since the rules were first evaluated
+    age: f64,
+}
+
+impl Absent {
+    /// The message name and sender a rule requires of every entry it matches,
+    /// from the predicates joined by `and` at its top level.
+    fn of(expr: &Expr) -> Option<Self> {
+        let mut absent = Self {
+            name: String::new(),
+            message_id: 0,
+            sys_id: None,
+            comp_id: None,
+            age: 0.0,
+        };
+        absent.collect(expr);
+        (!absent.name.is_empty()).then_some(absent)
+    }
+
+    fn collect(&mut self, expr: &Expr) {
+        let number = |literal: &Literal| match literal {
+            Literal::Number(n) => u8::try_from(*n as i64).ok(),
+            _ => None,
+        };
+        match expr {
+            Expr::And(a, b) => {
+                self.collect(a);
+                self.collect(b);
+            }
+            Expr::Contains(word) => self.name_message(word),
+            Expr::Compare {
+                operand: Operand::Name,
+                op: Op::Eq,
+                literal: Literal::Text(glob),
+            } => {
+                if let Some(name) = glob
+                    .as_str()
+                    .strip_prefix('^')
+                    .and_then(|re| re.strip_suffix('$'))
+                {
+                    self.name_message(name);
+                }
+            }
+            Expr::Compare {
+                operand: Operand::Sys,
+                op: Op::Eq,
+                literal,
+            } => self.sys_id = number(literal),
+            Expr::Compare {
+                operand: Operand::Comp,
+                op: Op::Eq,
+                literal,
+            } => self.comp_id = number(literal),
+            _ => {}
+        }
+    }
+
+    /// Takes `word` as the message name if it is one exactly.
+    fn name_message(&mut self, word: &str) {
+        let name = word.to_uppercase();
+        if let Ok(id) = MavMessage::message_id_from_name(&name) {
+            self.name = name;
+            self.message_id = id;
+        }
+    }
+
+    fn is<S: Subject + ?Sized>(&self, entry: &S) -> bool {
+        entry.name() == self.name
+            && self.sys_id.is_none_or(|sys| entry.system_id() == sys)
+            && self.comp_id.is_none_or(|comp| entry.component_id() == comp)
+    }
+}
+
+impl Subject for Absent {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn system_id(&self) -> u8 {
+        self.sys_id.unwrap_or(0)
+    }
+
+    fn component_id(&self) -> u8 {
+        self.comp_id.unwrap_or(0)
+    }
+
+    fn message_id(&self) -> u32 {
+        self.message_id
+    }
+
+    fn hz(&self) -> Option<f64> {
+        Some(0.0)
+    }
+
+    fn count(&self) -> Option<u64> {
+        Some(0)
+    }
+
+    fn age(&self) -> Option<f64> {
+        Some(self.age)
+    }
+
+    fn field(&self, _path: &str) -> Option<Value> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    struct Entry {
+        name: &'static str,
+        hz: f64,
+        age: f64,
+        first_received: Instant,
+    }
+
+    impl Subject for Entry {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn system_id(&self) -> u8 {
+            1
+        }
+
+        fn component_id(&self) -> u8 {
+            1
+        }
+
+        fn message_id(&self) -> u32 {
+            0
+        }
+
+        fn hz(&self) -> Option<f64> {
+            Some(self.hz)
+        }
+
+        fn age(&self) -> Option<f64> {
+            Some(self.age)
+        }
+
+        fn field(&self, _path: &str) -> Option<Value> {
+            None
+        }
+    }
+
+    impl Monitored for Entry {
+        fn first_received(&self) -> Instant {
+            self.first_received
+        }
+    }
+
+    fn alerts(rule: &str) -> Alerts {
+        Alerts {
+            rules: vec![AlertRule::parse(rule).unwrap()],
+            ..Alerts::default()
+        }
+    }
+
+    fn attitude(hz: f64, start: Instant) -> Entry {
+        Entry {
+            name: "ATTITUDE",
+            hz,
+            age: 0.0,
+            first_received: start,
+        }
+    }
+
+    fn events(alerts: &mut Alerts) -> Vec<String> {
+        alerts
+            .take_new()
+            .iter()
+            .map(|e| format!("{} {}", e.triggered, e.message))
+            .collect()
+    }
+
+    #[test]
+    fn triggers_and_clears_after_the_hold_time() {
+        let mut alerts = alerts("ATTITUDE and hz < 20");
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(5000 + ms);
+        // A rate hovering around the threshold does not flap
+        for (ms, hz) in [(0, 19.0), (300, 21.0), (600, 19.0), (900, 21.0)] {
+            alerts.evaluate(&[attitude(hz, start)], at(ms), 5.0);
+        }
+        assert!(events(&mut alerts).is_empty());
+
+        alerts.evaluate(&[attitude(15.0, start)], at(1000), 5.0);
+        alerts.evaluate(&[attitude(15.0, start)], at(1900), 5.0);
+        assert_eq!(alerts.active_count(), 0);
+        alerts.evaluate(&[attitude(15.0, start)], at(2000), 5.0);
+        assert_eq!(events(&mut alerts), ["true ATTITUDE"]);
+        assert!(alerts.is_active(0));
+
+        alerts.evaluate(&[attitude(25.0, start)], at(2100), 5.0);
+        alerts.evaluate(&[attitude(15.0, start)], at(2500), 5.0);
+        assert!(alerts.is_active(0), "a short recovery does not clear");
+        alerts.evaluate(&[attitude(25.0, start)], at(2600), 5.0);
+        alerts.evaluate(&[attitude(25.0, start)], at(3600), 5.0);
+        assert_eq!(events(&mut alerts), ["false ATTITUDE"]);
+    }
+
+    #[test]
+    fn a_message_never_received_ages_from_the_start() {
+        let mut alerts = alerts("HEARTBEAT and sys == 1 and comp == 1 and age > 3");
+        alerts.hold_secs = 0.0;
+        let start = Instant::now();
+        alerts.evaluate(&[attitude(50.0, start)], start, 5.0);
+        let now = start + Duration::from_secs(2);
+        alerts.evaluate(&[attitude(50.0, now)], now, 5.0);
+        assert!(
+            events(&mut alerts).is_empty(),
+            "not missing for long enough yet"
+        );
+        let now = start + Duration::from_secs(4);
+        alerts.evaluate(&[attitude(50.0, now)], now, 5.0);
+        assert_eq!(events(&mut alerts), ["true HEARTBEAT"]);
+        assert_eq!(alerts.active_count(), 1);
+
+        let heartbeat = Entry {
+            name: "HEARTBEAT",
+            hz: 1.0,
+            age: 0.5,
+            first_received: now,
+        };
+        alerts.evaluate(&[attitude(50.0, now), heartbeat], now, 5.0);
+        assert_eq!(events(&mut alerts), ["false HEARTBEAT"]);
+        assert_eq!(alerts.active_count(), 0);
+    }
+
+    #[test]
+    fn rules_without_a_message_name_ignore_absent_entries() {
+        let mut alerts = alerts("age > 3 or GPS*");
+        alerts.hold_secs = 0.0;
+        alerts.evaluate::<Entry>(&[], Instant::now(), 5.0);
+        assert_eq!(alerts.active_count(), 0);
+    }
+
+    #[test]
+    fn keeps_a_bounded_log() {
+        let mut alerts = alerts("ATTITUDE and hz < 20");
+        alerts.hold_secs = 0.0;
+        let start = Instant::now();
+        for n in 0..LOG_LEN + 10 {
+            let hz = if n % 2 == 0 { 10.0 } else { 30.0 };
+            let at = start + Duration::from_secs(10 + n as u64);
+            alerts.evaluate(&[attitude(hz, start)], at, 5.0);
+        }
+        // The first ten of the alternating events were dropped
+        assert_eq!(alerts.log.len(), LOG_LEN);
+        assert!(alerts.log[0].triggered);
+        assert!(!alerts.log[LOG_LEN - 1].triggered);
+    }
+}
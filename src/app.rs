@@ -1,25 +1,353 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::time::Instant;
 
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use chrono::{DateTime, Local, Utc};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
 use ratatui::widgets::TableState;
 
+use mavlink::ardupilotmega::MavMessage;
 use mavlink::Message;
 
-use crate::mavlink_io::ReceivedMessage;
+use crate::alert::{Alerts, Monitored};
+use crate::console::Console;
+use crate::fields::{self, FieldRow};
+use crate::filter::{self, Expr, Operand, Subject, Value};
+use crate::keymap::Keymap;
+use crate::link::{Connection, LinkHealth, SequenceStats};
+use crate::mavlink_io::{LinkError, LinkEvent, ReceivedMessage};
+use crate::mouse::{HitAreas, Split, WHEEL_LINES};
+use crate::schema;
+use crate::share::{self, ConnectionSnapshot, EntrySnapshot, MessageSnapshot, TableSnapshot};
+use crate::state::{EntryKey, SavedState};
+use crate::stats::IntervalStats;
+use crate::theme::Theme;
+use crate::vehicle::VehicleState;
+use crate::watch::Watch;
 
-const HZ_WINDOW_SECS: f64 = 5.0;
+pub const DEFAULT_HZ_WINDOW_SECS: f64 = 5.0;
+/// Number of recent intervals kept per entry for the histogram
+const HISTOGRAM_SAMPLES: usize = 1000;
+pub const DEFAULT_PLOT_WINDOW_SECS: f64 = 30.0;
+/// Shortest and longest plot window accepted from the config or `--plot-window`
+pub const MIN_PLOT_WINDOW_SECS: f64 = 1.0;
+pub const MAX_PLOT_WINDOW_SECS: f64 = 3600.0;
+pub const DEFAULT_HISTORY_LEN: usize = 200;
+/// Width of the Link column of the message table
+pub const LINK_COLUMN_WIDTH: u16 = 10;
 
+#[derive(Clone)]
 pub struct MessageEntry {
     pub name: String,
     pub sys_id: u8,
     pub comp_id: u8,
+    /// Connection this entry counts; `None` when entries are merged across connections
+    pub link: Option<usize>,
+    /// Connections the message has arrived on, and their names joined by `+`
+    pub seen_on: BTreeSet<usize>,
+    pub via: String,
     pub count: u64,
     pub hz: f64,
-    pub last_content: String,
-    timestamps: VecDeque<Instant>,
+    pub bytes_per_sec: f64,
+    pub total_bytes: u64,
+    /// Field tree of `last_message()`, with the time each field last changed.
+    /// Decoded lazily by `update_fields`, since most entries are never shown.
+    pub fields: Vec<FieldRow>,
+    /// `last_message()` may differ from `fields`
+    fields_stale: bool,
+    /// When `last_message()` last changed, or last arrived if not compared
+    changed_at: Instant,
+    /// Inter-arrival statistics over the whole session
+    pub intervals: IntervalStats,
+    /// Most recent inter-arrival intervals in seconds
+    pub recent_intervals: VecDeque<f64>,
+    pub first_received: Instant,
+    pub last_received: Instant,
+    /// Seconds since `last_received`, updated every tick
+    pub age: f64,
+    /// (receive time, frame length) of each message inside the Hz window
+    timestamps: VecDeque<(Instant, usize)>,
+    /// The last `history_len` instances, oldest first; never empty
+    pub history: VecDeque<HistoryItem>,
+    /// Left out of the table; mirrors `SavedState::hidden`
+    pub hidden: bool,
+    /// Kept at the top of the table; mirrors `SavedState::pinned`
+    pub pinned_top: bool,
+}
+
+#[derive(Clone)]
+pub struct HistoryItem {
+    pub received_at: Instant,
+    /// Wall-clock arrival time
+    pub time: DateTime<Utc>,
+    pub message: MavMessage,
+}
+
+/// The save-message prompt, holding the message as it was when opened.
+pub struct SavePrompt {
+    pub path: String,
+    text: String,
+    snapshot: MessageSnapshot,
+}
+
+/// One past instance of a message, decoded for the history view.
+pub struct HistoryView<'a> {
+    pub item: &'a HistoryItem,
+    /// Field rows with `changed_at` set on the fields that differ from `previous`
+    pub rows: Vec<FieldRow>,
+    pub previous: Option<(&'a HistoryItem, Vec<FieldRow>)>,
 }
 
+impl MessageEntry {
+    pub fn key(&self) -> EntryKey {
+        EntryKey {
+            sys_id: self.sys_id,
+            comp_id: self.comp_id,
+            message: self.name.clone(),
+        }
+    }
+
+    /// The instance `offset` steps back from the newest, if still in the buffer.
+    pub fn history_view(&self, offset: usize) -> Option<HistoryView<'_>> {
+        let idx = self.history.len().checked_sub(offset + 1)?;
+        let item = &self.history[idx];
+        let mut rows = fields::field_rows(&item.message);
+        let previous = idx
+            .checked_sub(1)
+            .map(|prev| &self.history[prev])
+            .map(|prev| (prev, fields::field_rows(&prev.message)));
+        if let Some((_, prev_rows)) = &previous {
+            fields::mark_changes(&mut rows, prev_rows, item.received_at);
+        }
+        Some(HistoryView {
+            item,
+            rows,
+            previous,
+        })
+    }
+
+    pub fn last_message(&self) -> &MavMessage {
+        &self.history[self.history.len() - 1].message
+    }
+
+    /// Decodes `last_message()` into `fields` if it changed since the last call.
+    /// Fields that differ are stamped with the time of the latest change.
+    pub fn update_fields(&mut self) {
+        if !self.fields_stale {
+            return;
+        }
+        let mut rows = fields::field_rows(self.last_message());
+        fields::mark_changes(&mut rows, &self.fields, self.changed_at);
+        self.fields = rows;
+        self.fields_stale = false;
+    }
+
+    fn push_history(&mut self, item: HistoryItem, capacity: usize) {
+        while self.history.len() >= capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(item);
+    }
+}
+
+impl Subject for MessageEntry {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn system_id(&self) -> u8 {
+        self.sys_id
+    }
+
+    fn component_id(&self) -> u8 {
+        self.comp_id
+    }
+
+    fn message_id(&self) -> u32 {
+        self.last_message().message_id()
+    }
+
+    fn hz(&self) -> Option<f64> {
+        Some(self.hz)
+    }
+
+    fn count(&self) -> Option<u64> {
+        Some(self.count)
+    }
+
+    fn age(&self) -> Option<f64> {
+        Some(self.age)
+    }
+
+    fn link(&self) -> Option<String> {
+        Some(self.via.clone())
+    }
+
+    fn field(&self, path: &str) -> Option<Value> {
+        if self.fields_stale {
+            filter::row_field(&fields::field_rows(self.last_message()), path)
+        } else {
+            filter::row_field(&self.fields, path)
+        }
+    }
+}
+
+impl Monitored for MessageEntry {
+    fn first_received(&self) -> Instant {
+        self.first_received
+    }
+}
+
+/// Everything incoming traffic changes. While paused the view keeps a frozen
+/// copy and the live one goes on in `App::live`.
+#[derive(Clone)]
+struct Capture {
+    connections: Vec<Connection>,
+    entries: Vec<MessageEntry>,
+    index: HashMap<(Option<usize>, u8, u8, u32), usize>,
+    total_count: u64,
+    links: BTreeMap<(usize, u8, u8), SequenceStats>,
+    health: LinkHealth,
+    vehicles: BTreeMap<(u8, u8), VehicleState>,
+    console: Console,
+    watches: Vec<Watch>,
+    pinned: Vec<PinnedField>,
+}
+
+fn link_names(connections: &[Connection], links: &BTreeSet<usize>) -> String {
+    let names: Vec<&str> = links
+        .iter()
+        .filter_map(|&link| connections.get(link))
+        .map(|c| c.name.as_str())
+        .collect();
+    names.join("+")
+}
+
+#[derive(PartialEq)]
+pub enum Focus {
+    Table,
+    Detail,
+    Console,
+    Watch,
+}
+
+/// A numeric field pinned to the plot panel, with its samples over the plot window.
+#[derive(Clone)]
+pub struct PinnedField {
+    pub link: Option<usize>,
+    pub sys_id: u8,
+    pub comp_id: u8,
+    pub message: String,
+    pub path: String,
+    pub samples: VecDeque<(Instant, f64)>,
+}
+
+impl PinnedField {
+    pub fn label(&self) -> String {
+        format!(
+            "{}.{} [{}:{}]",
+            self.message, self.path, self.sys_id, self.comp_id
+        )
+    }
+
+    fn matches(&self, entry: &MessageEntry) -> bool {
+        self.follows(entry.link, (entry.sys_id, entry.comp_id), &entry.name)
+    }
+
+    fn follows(&self, link: Option<usize>, sender: (u8, u8), message: &str) -> bool {
+        self.link == link && (self.sys_id, self.comp_id) == sender && self.message == message
+    }
+
+    fn same_field(&self, other: &PinnedField) -> bool {
+        self.link == other.link
+            && self.sys_id == other.sys_id
+            && self.comp_id == other.comp_id
+            && self.message == other.message
+            && self.path == other.path
+    }
+}
+
+/// Optional panel shown below the message table
+#[derive(PartialEq, Clone, Copy)]
+pub enum Panel {
+    Links,
+    Throughput,
+    Vehicles,
+    Console,
+    Alerts,
+    Watch,
+}
+
+/// Dialect schema browser state; `matches` indexes `schema::messages()`.
+pub struct SchemaBrowser {
+    pub selected: usize,
+    pub search: String,
+    pub search_active: bool,
+    pub matches: Vec<usize>,
+    pub scroll: usize,
+}
+
+impl SchemaBrowser {
+    fn new(message: Option<&str>) -> Self {
+        let mut browser = Self {
+            selected: 0,
+            search: String::new(),
+            search_active: false,
+            matches: Vec::new(),
+            scroll: 0,
+        };
+        browser.update_matches();
+        if let Some(name) = message {
+            let messages = schema::messages();
+            if let Some(pos) = browser
+                .matches
+                .iter()
+                .position(|&i| messages[i].name == name)
+            {
+                browser.selected = pos;
+            }
+        }
+        browser
+    }
+
+    fn update_matches(&mut self) {
+        let search = self.search.to_uppercase();
+        self.matches = schema::messages()
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m.name.contains(&search) || m.id.to_string() == search)
+            .map(|(i, _)| i)
+            .collect();
+        self.selected = self.selected.min(self.matches.len().saturating_sub(1));
+        self.scroll = 0;
+    }
+
+    pub fn selected_message(&self) -> Option<&'static schema::MessageDef> {
+        self.matches
+            .get(self.selected)
+            .map(|&i| &schema::messages()[i])
+    }
+}
+
+/// A row of the message table. In the flat view every row is a message;
+/// the tree view adds system and component nodes with aggregate rates.
+pub enum TableRow {
+    System {
+        sys_id: u8,
+        hz: f64,
+        count: u64,
+    },
+    Component {
+        sys_id: u8,
+        comp_id: u8,
+        hz: f64,
+        count: u64,
+    },
+    Message(usize),
+}
+
+#[derive(Clone, Copy, PartialEq)]
 pub enum SortMode {
     Alphabetical,
     Hz,
@@ -27,24 +355,87 @@ pub enum SortMode {
 }
 
 pub struct App {
-    pub uri: String,
+    pub connections: Vec<Connection>,
+    /// Count a (sys, comp, message) once across connections instead of per connection
+    pub merge_links: bool,
     pub heartbeat: Option<(u8, u8)>,
     pub entries: Vec<MessageEntry>,
-    index: HashMap<(u8, u8, String), usize>,
+    /// Entry by (connection unless merged, sys_id, comp_id, message id)
+    index: HashMap<(Option<usize>, u8, u8, u32), usize>,
     pub selected: usize,
     pub detail_scroll: usize,
     pub filter: String,
     pub filter_active: bool,
+    /// Parsed `filter`; the last valid expression stays active while the text has errors
+    filter_expr: Option<Expr>,
+    pub filter_error: Option<String>,
     pub filtered_indices: Vec<usize>,
+    /// Entries changed since `rows` was built; `refresh` rebuilds it once per frame
+    dirty: bool,
+    /// What the table shows: `filtered_indices` as is, or grouped into a tree
+    pub rows: Vec<TableRow>,
+    pub tree_view: bool,
+    /// Collapsed tree nodes: (sys_id, None) for systems, (sys_id, Some(comp_id)) for components
+    pub collapsed: HashSet<(u8, Option<u8>)>,
     pub total_count: u64,
     pub table_state: TableState,
     pub sort_mode: SortMode,
+    pub focus: Focus,
+    /// Field cursor in the detail pane
+    pub field_selected: usize,
+    pub pinned: Vec<PinnedField>,
+    pub plot_shared_axes: bool,
+    pub plot_window_secs: f64,
+    /// Sequence-number statistics per connection and (sys_id, comp_id) sender
+    pub links: BTreeMap<(usize, u8, u8), SequenceStats>,
+    pub panel: Option<Panel>,
+    /// Receive counters over all connections; each connection also has its own
+    pub health: LinkHealth,
+    /// Show the inter-arrival histogram instead of the field tree
+    pub show_histogram: bool,
+    /// Show field values scaled and named from the dialect metadata
+    pub humanize: bool,
+    /// HEARTBEAT/SYS_STATUS state per (sys_id, comp_id), for the vehicle panel
+    pub vehicles: BTreeMap<(u8, u8), VehicleState>,
+    pub console: Console,
+    pub history_len: usize,
+    /// Instances back from the newest shown in the detail pane; 0 is live
+    pub history_offset: usize,
+    /// Set while the view is frozen
+    pub paused_at: Option<Instant>,
+    /// The capture incoming traffic goes to while the view shows a frozen copy
+    live: Option<Box<Capture>>,
+    /// Hidden and pinned entries, persisted across sessions
+    pub saved: SavedState,
+    /// Open dialect schema browser, replacing the table and detail pane
+    pub schema: Option<SchemaBrowser>,
+    pub alerts: Alerts,
+    pub watches: Vec<Watch>,
+    /// Watch under the cursor while the watch panel has focus
+    pub watch_selected: usize,
+    /// Text of the add-watch prompt while it is open
+    pub watch_input: Option<String>,
+    pub watch_error: Option<String>,
+    /// Save-message prompt while it is open
+    pub save_prompt: Option<SavePrompt>,
+    /// Outcome of the last copy, save or snapshot, until the next key
+    pub status: Option<String>,
+    /// Table/detail areas of the last frame, for mouse hit testing
+    pub hit: HitAreas,
+    /// Header cells of the message table drawn last frame and the sort each selects
+    pub sort_columns: Vec<(Rect, SortMode)>,
+    pub split: Split,
+    /// Seconds of traffic the Hz and bytes/s figures average over
+    pub hz_window_secs: f64,
+    pub keymap: Keymap,
+    pub theme: Theme,
 }
 
 impl App {
-    pub fn new(uri: String, heartbeat: Option<(u8, u8)>) -> Self {
+    pub fn new(connections: Vec<Connection>, heartbeat: Option<(u8, u8)>) -> Self {
         Self {
-            uri,
+            connections,
+            merge_links: false,
             heartbeat,
             entries: Vec::new(),
             index: HashMap::new(),
@@ -52,54 +443,346 @@ impl App {
             detail_scroll: 0,
             filter: String::new(),
             filter_active: false,
+            filter_expr: None,
+            filter_error: None,
             filtered_indices: Vec::new(),
+            dirty: false,
+            rows: Vec::new(),
+            tree_view: false,
+            collapsed: HashSet::new(),
             total_count: 0,
             table_state: TableState::default(),
             sort_mode: SortMode::Alphabetical,
+            focus: Focus::Table,
+            field_selected: 0,
+            pinned: Vec::new(),
+            plot_shared_axes: true,
+            plot_window_secs: DEFAULT_PLOT_WINDOW_SECS,
+            links: BTreeMap::new(),
+            panel: None,
+            health: LinkHealth::default(),
+            show_histogram: false,
+            humanize: false,
+            vehicles: BTreeMap::new(),
+            console: Console::default(),
+            history_len: DEFAULT_HISTORY_LEN,
+            history_offset: 0,
+            paused_at: None,
+            live: None,
+            saved: SavedState::default(),
+            schema: None,
+            alerts: Alerts::default(),
+            watches: Vec::new(),
+            watch_selected: 0,
+            watch_input: None,
+            watch_error: None,
+            save_prompt: None,
+            status: None,
+            hit: HitAreas::default(),
+            sort_columns: Vec::new(),
+            split: Split::default(),
+            hz_window_secs: DEFAULT_HZ_WINDOW_SECS,
+            keymap: Keymap::default(),
+            theme: Theme::dark(),
+        }
+    }
+
+    pub fn on_event(&mut self, event: LinkEvent) {
+        self.with_live(|app| match event {
+            LinkEvent::Message(msg) => app.on_message(msg),
+            LinkEvent::Error(error) => app.on_link_error(error),
+        });
+    }
+
+    /// Freezes the view on a copy of the capture, or resumes on the live one,
+    /// which kept up with the link meanwhile.
+    fn toggle_pause(&mut self) {
+        match self.live.take() {
+            None => {
+                self.paused_at = Some(Instant::now());
+                self.live = Some(Box::new(Capture {
+                    connections: self.connections.clone(),
+                    entries: self.entries.clone(),
+                    index: self.index.clone(),
+                    total_count: self.total_count,
+                    links: self.links.clone(),
+                    health: self.health.clone(),
+                    vehicles: self.vehicles.clone(),
+                    console: self.console.clone(),
+                    watches: self.watches.clone(),
+                    pinned: self.pinned.clone(),
+                }));
+            }
+            Some(mut capture) => {
+                self.paused_at = None;
+                self.swap_capture(&mut capture);
+                let frozen = capture;
+                self.console.keep_view_of(&frozen.console);
+                // Stay on the same past instance of the selected entry
+                if let Some(idx) = self.selected_index().filter(|_| self.history_offset > 0) {
+                    let entry = &self.entries[idx];
+                    let arrived = (entry.count - frozen.entries[idx].count) as usize;
+                    self.history_offset =
+                        (self.history_offset + arrived).min(entry.history.len() - 1);
+                }
+                self.dirty = true;
+            }
+        }
+    }
+
+    /// Runs `apply` on the live capture: the shown one, or the one behind the
+    /// frozen view while paused. Entries are only ever appended, so the view's
+    /// entry indices hold in both.
+    fn with_live(&mut self, apply: impl FnOnce(&mut Self)) {
+        let Some(mut live) = self.live.take() else {
+            apply(self);
+            return;
+        };
+        let (dirty, history_offset) = (self.dirty, self.history_offset);
+        self.swap_capture(&mut live);
+        apply(self);
+        self.swap_capture(&mut live);
+        self.live = Some(live);
+        self.dirty = dirty;
+        self.history_offset = history_offset;
+    }
+
+    /// Applies a change made by the user to the shown capture and, while
+    /// paused, to the live one as well.
+    fn edit_captures(&mut self, edit: impl Fn(&mut Self)) {
+        edit(self);
+        if self.live.is_some() {
+            self.with_live(&edit);
+        }
+    }
+
+    fn swap_capture(&mut self, capture: &mut Capture) {
+        std::mem::swap(&mut self.connections, &mut capture.connections);
+        std::mem::swap(&mut self.entries, &mut capture.entries);
+        std::mem::swap(&mut self.index, &mut capture.index);
+        std::mem::swap(&mut self.total_count, &mut capture.total_count);
+        std::mem::swap(&mut self.links, &mut capture.links);
+        std::mem::swap(&mut self.health, &mut capture.health);
+        std::mem::swap(&mut self.vehicles, &mut capture.vehicles);
+        std::mem::swap(&mut self.console, &mut capture.console);
+        std::mem::swap(&mut self.watches, &mut capture.watches);
+        std::mem::swap(&mut self.pinned, &mut capture.pinned);
+    }
+
+    fn on_link_error(&mut self, error: LinkError) {
+        self.health.on_error(&error);
+        if let Some(connection) = self.connections.get_mut(error.link) {
+            connection.health.on_error(&error);
         }
     }
 
-    pub fn on_message(&mut self, msg: ReceivedMessage) {
+    fn on_message(&mut self, msg: ReceivedMessage) {
         self.total_count += 1;
-        let name = msg.message.message_name().to_string();
-        let key = (msg.header.system_id, msg.header.component_id, name.clone());
-        let content = format!("{:#?}", msg.message);
+        self.health.on_frame(msg.received_at, msg.frame_len);
+        if let Some(connection) = self.connections.get_mut(msg.link) {
+            connection.health.on_frame(msg.received_at, msg.frame_len);
+        }
+        let sender = (msg.header.system_id, msg.header.component_id);
+        self.links
+            .entry((msg.link, sender.0, sender.1))
+            .or_default()
+            .update(msg.header.sequence, msg.timestamp);
+        if matches!(
+            msg.message,
+            MavMessage::HEARTBEAT(_) | MavMessage::SYS_STATUS(_)
+        ) {
+            self.vehicles
+                .entry((msg.header.system_id, msg.header.component_id))
+                .or_default()
+                .update(&msg.message, msg.received_at);
+        }
+        self.console.push(&msg);
+        let link = (!self.merge_links).then_some(msg.link);
+        let key = (link, sender.0, sender.1, msg.message.message_id());
+        let name = msg.message.message_name();
+        let watched = self.watches.iter().any(|w| w.message == name);
+        let plotted = self.pinned.iter().any(|p| p.follows(link, sender, name));
+        let item = HistoryItem {
+            received_at: msg.received_at,
+            time: msg.timestamp,
+            message: msg.message,
+        };
 
-        if let Some(&idx) = self.index.get(&key) {
+        let idx = if let Some(&idx) = self.index.get(&key) {
+            // Field changes are only worth finding for messages on screen;
+            // the others are decoded afresh once shown
+            let compared = watched || plotted || self.selected_index() == Some(idx);
             let entry = &mut self.entries[idx];
             entry.count += 1;
-            entry.last_content = content;
-            entry.timestamps.push_back(msg.received_at);
+            if entry.seen_on.insert(msg.link) {
+                entry.via = link_names(&self.connections, &entry.seen_on);
+            }
+            if !compared || *entry.last_message() != item.message {
+                entry.fields_stale = true;
+                entry.changed_at = msg.received_at;
+            }
+            entry.push_history(item, self.history_len);
+            entry.total_bytes += msg.frame_len as u64;
+            let interval = msg
+                .received_at
+                .duration_since(entry.last_received)
+                .as_secs_f64();
+            entry.intervals.add(interval);
+            if entry.recent_intervals.len() == HISTOGRAM_SAMPLES {
+                entry.recent_intervals.pop_front();
+            }
+            entry.recent_intervals.push_back(interval);
+            entry.last_received = msg.received_at;
+            entry.timestamps.push_back((msg.received_at, msg.frame_len));
+            idx
         } else {
             let idx = self.entries.len();
             let mut timestamps = VecDeque::new();
-            timestamps.push_back(msg.received_at);
+            timestamps.push_back((msg.received_at, msg.frame_len));
+            let seen_on = BTreeSet::from([msg.link]);
             self.entries.push(MessageEntry {
-                name: name.clone(),
+                name: name.to_string(),
                 sys_id: msg.header.system_id,
                 comp_id: msg.header.component_id,
+                link,
+                via: link_names(&self.connections, &seen_on),
+                seen_on,
                 count: 1,
                 hz: 0.0,
-                last_content: content,
+                bytes_per_sec: 0.0,
+                total_bytes: msg.frame_len as u64,
+                fields: Vec::new(),
+                fields_stale: true,
+                changed_at: msg.received_at,
+                intervals: IntervalStats::default(),
+                recent_intervals: VecDeque::new(),
+                first_received: msg.received_at,
+                last_received: msg.received_at,
+                age: 0.0,
                 timestamps,
+                history: VecDeque::new(),
+                hidden: false,
+                pinned_top: false,
             });
+            let entry = &mut self.entries[idx];
+            let entry_key = entry.key();
+            entry.hidden = self.saved.hidden.contains(&entry_key);
+            entry.pinned_top = self.saved.pinned.contains(&entry_key);
+            self.entries[idx].push_history(item, self.history_len);
             self.index.insert(key, idx);
+            idx
+        };
+
+        // Keep a history view on the same instance while new ones arrive
+        if self.history_offset > 0 && self.selected_index() == Some(idx) {
+            let len = self.entries[idx].history.len();
+            self.history_offset = (self.history_offset + 1).min(len - 1);
         }
 
-        self.rebuild_filter();
+        // Only watched and plotted messages are decoded as they arrive
+        let entry = &mut self.entries[idx];
+        if watched || plotted {
+            entry.update_fields();
+        }
+        if watched {
+            for watch in &mut self.watches {
+                watch.update(
+                    (entry.sys_id, entry.comp_id),
+                    &entry.name,
+                    &entry.fields,
+                    msg.received_at,
+                );
+            }
+        }
+        if plotted {
+            self.sample_pins(idx, msg.received_at);
+        }
+        self.dirty = true;
+    }
+
+    /// Brings what the next frame shows up to date: the table if entries
+    /// changed since the last frame, and the decoded fields of the selected
+    /// entry and of the message open in the dialect browser.
+    pub fn refresh(&mut self) {
+        if self.dirty {
+            self.rebuild_filter();
+        }
+        if let Some(idx) = self.selected_index() {
+            self.entries[idx].update_fields();
+        }
+        if let Some(def) = self.schema.as_ref().and_then(|b| b.selected_message()) {
+            for entry in self.entries.iter_mut().filter(|e| e.name == def.name) {
+                entry.update_fields();
+            }
+        }
     }
 
+    fn sample_pins(&mut self, idx: usize, at: Instant) {
+        let entry = &self.entries[idx];
+        for pin in self.pinned.iter_mut().filter(|p| p.matches(entry)) {
+            let value = entry
+                .fields
+                .iter()
+                .find(|row| row.path == pin.path)
+                .and_then(|row| row.value.as_f64());
+            if let Some(value) = value {
+                pin.samples.push_back((at, value));
+            }
+        }
+    }
+
+    /// Updates rates, ages and alerts; while paused only the live capture moves.
     pub fn tick(&mut self) {
+        self.with_live(Self::update_rates);
+    }
+
+    fn update_rates(&mut self) {
         let now = Instant::now();
+        let mut rates_changed = false;
         for entry in &mut self.entries {
-            while let Some(&front) = entry.timestamps.front() {
-                if now.duration_since(front).as_secs_f64() > HZ_WINDOW_SECS {
+            while let Some(&(front, _)) = entry.timestamps.front() {
+                if now.duration_since(front).as_secs_f64() > self.hz_window_secs {
                     entry.timestamps.pop_front();
                 } else {
                     break;
                 }
             }
-            entry.hz = entry.timestamps.len() as f64 / HZ_WINDOW_SECS;
+            let hz = entry.timestamps.len() as f64 / self.hz_window_secs;
+            rates_changed |= hz != entry.hz;
+            entry.hz = hz;
+            entry.age = now.duration_since(entry.last_received).as_secs_f64();
+            entry.bytes_per_sec = entry.timestamps.iter().map(|&(_, len)| len).sum::<usize>()
+                as f64
+                / self.hz_window_secs;
+        }
+        self.health.tick(now, self.hz_window_secs);
+        for connection in &mut self.connections {
+            connection.health.tick(now, self.hz_window_secs);
+        }
+
+        for pin in &mut self.pinned {
+            while let Some(&(front, _)) = pin.samples.front() {
+                if now.duration_since(front).as_secs_f64() > self.plot_window_secs {
+                    pin.samples.pop_front();
+                } else {
+                    break;
+                }
+            }
+        }
+
+        self.alerts
+            .evaluate(&self.entries, now, self.hz_window_secs);
+
+        // The Hz sort, the tree's totals and `hz` predicates follow the rates;
+        // `age` moves on every tick
+        let filter_uses = |wanted: fn(&Operand) -> bool| {
+            self.filter_expr.as_ref().is_some_and(|e| e.uses(&wanted))
+        };
+        let follows_rates = self.sort_mode == SortMode::Hz
+            || self.tree_view
+            || filter_uses(|o| matches!(o, Operand::Hz));
+        if (rates_changed && follows_rates) || filter_uses(|o| matches!(o, Operand::Age)) {
+            self.dirty = true;
         }
     }
 
@@ -108,34 +791,189 @@ impl App {
             return true;
         }
 
+        self.status = None;
+
+        if let Some(prompt) = &mut self.save_prompt {
+            match key.code {
+                KeyCode::Esc => self.save_prompt = None,
+                KeyCode::Enter => {
+                    if let Some(prompt) = self.save_prompt.take() {
+                        self.save_message(prompt);
+                    }
+                }
+                KeyCode::Tab => share::toggle_extension(&mut prompt.path),
+                KeyCode::Backspace => {
+                    prompt.path.pop();
+                }
+                KeyCode::Char(c) => prompt.path.push(c),
+                _ => {}
+            }
+            return false;
+        }
+
         if self.filter_active {
             match key.code {
                 KeyCode::Esc => {
                     self.filter.clear();
                     self.filter_active = false;
-                    self.rebuild_filter();
+                    self.parse_filter();
                 }
                 KeyCode::Enter => {
                     self.filter_active = false;
                 }
                 KeyCode::Backspace => {
                     self.filter.pop();
-                    self.rebuild_filter();
+                    self.parse_filter();
                 }
                 KeyCode::Char(c) => {
                     self.filter.push(c);
-                    self.rebuild_filter();
+                    self.parse_filter();
+                }
+                _ => {}
+            }
+            return false;
+        }
+
+        if self.console.search_active {
+            let search = &mut self.console.search;
+            match key.code {
+                KeyCode::Esc => {
+                    search.clear();
+                    self.console.search_active = false;
+                }
+                KeyCode::Enter => self.console.search_active = false,
+                KeyCode::Backspace => {
+                    search.pop();
+                }
+                KeyCode::Char(c) => search.push(c),
+                _ => {}
+            }
+            self.console.scroll = 0;
+            return false;
+        }
+
+        if let Some(input) = &mut self.watch_input {
+            match key.code {
+                KeyCode::Esc => {
+                    self.watch_input = None;
+                    self.watch_error = None;
+                }
+                KeyCode::Enter => {
+                    let spec = input.clone();
+                    self.add_watch(&spec);
                 }
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) => input.push(c),
                 _ => {}
             }
             return false;
         }
 
+        let typing = self.schema.as_ref().is_some_and(|b| b.search_active);
+        let key = if typing { key } else { self.keymap.apply(key) };
+
+        if self.schema.is_some() {
+            self.on_schema_key(key);
+            return false;
+        }
+        if self.focus == Focus::Detail && self.on_detail_key(key) {
+            return false;
+        }
+        if self.focus == Focus::Console && self.on_console_key(key) {
+            return false;
+        }
+        if self.focus == Focus::Watch && self.on_watch_key(key) {
+            return false;
+        }
+
         match key.code {
             KeyCode::Char('q') => return true,
+            KeyCode::Char(' ') => self.toggle_pause(),
             KeyCode::Char('/') => {
                 self.filter_active = true;
             }
+            KeyCode::Tab if self.selected_entry().is_some() => {
+                self.focus = Focus::Detail;
+            }
+            KeyCode::Char('P') => self.edit_captures(|app| app.pinned.clear()),
+            KeyCode::Char('x') => self.toggle_saved(|entry| &mut entry.hidden, |s| &mut s.hidden),
+            KeyCode::Char('X') => {
+                self.saved.hidden.clear();
+                self.edit_captures(|app| {
+                    for entry in &mut app.entries {
+                        entry.hidden = false;
+                    }
+                });
+                self.save_state();
+            }
+            KeyCode::Char('p') => {
+                self.toggle_saved(|entry| &mut entry.pinned_top, |s| &mut s.pinned)
+            }
+            KeyCode::Char('l') => self.toggle_panel(Panel::Links),
+            KeyCode::Char('t') => self.toggle_panel(Panel::Throughput),
+            KeyCode::Char('v') => self.toggle_panel(Panel::Vehicles),
+            KeyCode::Char('A') => self.toggle_panel(Panel::Alerts),
+            KeyCode::Char('W') => {
+                // An open but unfocused panel (e.g. from --watch) takes focus first
+                if self.panel != Some(Panel::Watch) || self.focus == Focus::Watch {
+                    self.toggle_panel(Panel::Watch);
+                }
+                if self.panel == Some(Panel::Watch) {
+                    self.focus = Focus::Watch;
+                }
+            }
+            KeyCode::Char('w') => self.open_watch_prompt(),
+            KeyCode::Char('y') => self.copy_message(),
+            KeyCode::Char('S') => {
+                if let Some((text, snapshot)) = self.message_snapshot() {
+                    let path = format!(
+                        "{}-{}-{}-{}.json",
+                        snapshot.message_name,
+                        snapshot.system_id,
+                        snapshot.component_id,
+                        snapshot.time.format("%Y%m%d-%H%M%S")
+                    );
+                    self.save_prompt = Some(SavePrompt {
+                        path,
+                        text,
+                        snapshot,
+                    });
+                }
+            }
+            KeyCode::Char('E') => self.write_table_snapshot(),
+            KeyCode::Char('c') => {
+                self.toggle_panel(Panel::Console);
+                if self.panel == Some(Panel::Console) {
+                    self.focus = Focus::Console;
+                }
+            }
+            KeyCode::Char('h') => {
+                self.show_histogram = !self.show_histogram;
+            }
+            KeyCode::Char('r') => {
+                self.humanize = !self.humanize;
+            }
+            KeyCode::Char('[') => self.step_history(1),
+            KeyCode::Char(']') => self.step_history(-1),
+            KeyCode::Char('{') => self.step_history(10),
+            KeyCode::Char('}') => self.step_history(-10),
+            KeyCode::Char('a') => {
+                self.plot_shared_axes = !self.plot_shared_axes;
+            }
+            KeyCode::Char('D') => {
+                let name = self.selected_entry().map(|e| e.name.as_str());
+                self.schema = Some(SchemaBrowser::new(name));
+            }
+            KeyCode::Char('T') => {
+                self.tree_view = !self.tree_view;
+                self.selected = 0;
+                self.rebuild_filter();
+            }
+            KeyCode::Enter => self.toggle_collapsed(None),
+            KeyCode::Left => self.toggle_collapsed(Some(true)),
+            KeyCode::Right => self.toggle_collapsed(Some(false)),
             KeyCode::Char('s') => {
                 self.sort_mode = match self.sort_mode {
                     SortMode::Alphabetical => SortMode::Hz,
@@ -144,52 +982,564 @@ impl App {
                 };
                 self.rebuild_filter();
             }
-            KeyCode::Up | KeyCode::Char('k') => {
-                if self.selected > 0 {
-                    self.selected -= 1;
-                    self.detail_scroll = 0;
+            KeyCode::Up | KeyCode::Char('k') => self.select_row(self.selected.saturating_sub(1)),
+            KeyCode::Down | KeyCode::Char('j') => self.select_row(self.selected + 1),
+            KeyCode::Esc if !self.filter.is_empty() => {
+                self.filter.clear();
+                self.parse_filter();
+            }
+            KeyCode::PageDown | KeyCode::Char('d') => {
+                self.detail_scroll = self.detail_scroll.saturating_add(10);
+            }
+            KeyCode::PageUp | KeyCode::Char('u') => {
+                self.detail_scroll = self.detail_scroll.saturating_sub(10);
+            }
+            _ => {}
+        }
+        false
+    }
+
+    /// Moves the table cursor to `row` if it exists, resetting the detail
+    /// pane when the selection changes.
+    fn select_row(&mut self, row: usize) {
+        if row < self.rows.len() && row != self.selected {
+            self.selected = row;
+            self.detail_scroll = 0;
+            self.field_selected = 0;
+            self.history_offset = 0;
+        }
+        if !self.rows.is_empty() {
+            self.table_state.select(Some(self.selected));
+        }
+    }
+
+    pub fn on_mouse(&mut self, event: MouseEvent) {
+        if self.schema.is_some() || self.split.on_mouse(&event, &self.hit) {
+            return;
+        }
+        let (column, row) = (event.column, event.row);
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) if self.hit.on_table_header(column, row) => {
+                let Some(&(_, sort_mode)) = self
+                    .sort_columns
+                    .iter()
+                    .find(|(cell, _)| cell.contains(Position::new(column, row)))
+                else {
+                    return;
+                };
+                if sort_mode != self.sort_mode {
+                    self.sort_mode = sort_mode;
+                    self.rebuild_filter();
                 }
-                self.table_state.select(Some(self.selected));
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(visible) = self.hit.table_row(column, row) {
+                    self.focus = Focus::Table;
+                    self.select_row(self.table_state.offset() + visible);
+                }
+            }
+            MouseEventKind::ScrollDown if self.hit.in_detail(column, row) => {
+                self.detail_scroll = self.detail_scroll.saturating_add(WHEEL_LINES);
+            }
+            MouseEventKind::ScrollUp if self.hit.in_detail(column, row) => {
+                self.detail_scroll = self.detail_scroll.saturating_sub(WHEEL_LINES);
+            }
+            MouseEventKind::ScrollDown if self.hit.table_row(column, row).is_some() => {
+                self.select_row(self.selected + 1);
+            }
+            MouseEventKind::ScrollUp if self.hit.table_row(column, row).is_some() => {
+                self.select_row(self.selected.saturating_sub(1));
+            }
+            _ => {}
+        }
+    }
+
+    /// Keys that act on the field cursor while the detail pane has focus.
+    /// Returns false for keys that fall through to the table bindings.
+    fn on_detail_key(&mut self, key: KeyEvent) -> bool {
+        let field_count = self.selected_entry().map_or(0, |e| e.fields.len());
+        match key.code {
+            KeyCode::Tab | KeyCode::Esc => {
+                self.focus = Focus::Table;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.field_selected = self.field_selected.saturating_sub(1);
             }
             KeyCode::Down | KeyCode::Char('j') => {
-                if !self.filtered_indices.is_empty()
-                    && self.selected < self.filtered_indices.len() - 1
-                {
-                    self.selected += 1;
-                    self.detail_scroll = 0;
+                if self.field_selected + 1 < field_count {
+                    self.field_selected += 1;
                 }
-                self.table_state.select(Some(self.selected));
             }
-            KeyCode::Esc => {
-                if !self.filter.is_empty() {
-                    self.filter.clear();
-                    self.rebuild_filter();
+            KeyCode::Char('p') => self.toggle_pin(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Keys of the schema browser, which takes over the main area while open.
+    fn on_schema_key(&mut self, key: KeyEvent) {
+        let Some(browser) = self.schema.as_mut() else {
+            return;
+        };
+        if browser.search_active {
+            match key.code {
+                KeyCode::Esc => {
+                    browser.search.clear();
+                    browser.search_active = false;
+                }
+                KeyCode::Enter => browser.search_active = false,
+                KeyCode::Backspace => {
+                    browser.search.pop();
                 }
+                KeyCode::Char(c) => browser.search.push(c),
+                _ => return,
+            }
+            browser.update_matches();
+            return;
+        }
+
+        match key.code {
+            KeyCode::Esc if !browser.search.is_empty() => {
+                browser.search.clear();
+                browser.update_matches();
+            }
+            KeyCode::Esc | KeyCode::Char('D') | KeyCode::Char('q') => self.schema = None,
+            KeyCode::Char('/') => browser.search_active = true,
+            KeyCode::Up | KeyCode::Char('k') => {
+                browser.selected = browser.selected.saturating_sub(1);
+                browser.scroll = 0;
+            }
+            KeyCode::Down | KeyCode::Char('j') if browser.selected + 1 < browser.matches.len() => {
+                browser.selected += 1;
+                browser.scroll = 0;
             }
             KeyCode::PageDown | KeyCode::Char('d') => {
-                self.detail_scroll = self.detail_scroll.saturating_add(10);
+                browser.scroll = browser.scroll.saturating_add(10);
             }
             KeyCode::PageUp | KeyCode::Char('u') => {
-                self.detail_scroll = self.detail_scroll.saturating_sub(10);
+                browser.scroll = browser.scroll.saturating_sub(10);
+            }
+            KeyCode::Enter => {
+                let Some(def) = browser.selected_message() else {
+                    return;
+                };
+                if let Some(idx) = self.entries.iter().position(|e| e.name == def.name) {
+                    self.schema = None;
+                    self.reveal_entry(idx);
+                }
             }
             _ => {}
         }
-        false
+    }
+
+    /// Selects an entry in the table, clearing the filter and expanding tree
+    /// nodes if they hide it.
+    fn reveal_entry(&mut self, idx: usize) {
+        let entry = &self.entries[idx];
+        if entry.hidden {
+            return;
+        }
+        let (sys_id, comp_id) = (entry.sys_id, entry.comp_id);
+        if !self.filtered_indices.contains(&idx) {
+            self.filter.clear();
+            self.parse_filter();
+        }
+        self.collapsed.remove(&(sys_id, None));
+        self.collapsed.remove(&(sys_id, Some(comp_id)));
+        self.rebuild_filter();
+
+        if let Some(pos) = self
+            .rows
+            .iter()
+            .position(|row| matches!(row, TableRow::Message(i) if *i == idx))
+        {
+            self.selected = pos;
+            self.table_state.select(Some(pos));
+            self.detail_scroll = 0;
+            self.field_selected = 0;
+            self.history_offset = 0;
+        }
+    }
+
+    /// Keys that act on the console pane while it has focus.
+    /// Returns false for keys that fall through to the table bindings.
+    fn on_console_key(&mut self, key: KeyEvent) -> bool {
+        let console = &mut self.console;
+        match key.code {
+            KeyCode::Tab | KeyCode::Esc => {
+                self.focus = Focus::Table;
+            }
+            KeyCode::Char('/') => {
+                console.search_active = true;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let max = console.visible().len().saturating_sub(1);
+                console.scroll = (console.scroll + 1).min(max);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                console.scroll = console.scroll.saturating_sub(1);
+            }
+            KeyCode::Char('G') => {
+                console.scroll = 0;
+            }
+            KeyCode::Char(c @ '0'..='7') => {
+                console.max_severity = c as u8 - b'0';
+                console.scroll = 0;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Keys that act on the watch list while the watch panel has focus.
+    /// Returns false for keys that fall through to the table bindings.
+    fn on_watch_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Tab | KeyCode::Esc => {
+                self.focus = Focus::Table;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.watch_selected = self.watch_selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                if self.watch_selected + 1 < self.watches.len() {
+                    self.watch_selected += 1;
+                }
+            }
+            KeyCode::Char('x') | KeyCode::Delete if self.watch_selected < self.watches.len() => {
+                let selected = self.watch_selected;
+                self.edit_captures(|app| {
+                    app.watches.remove(selected);
+                });
+                self.watch_selected = self
+                    .watch_selected
+                    .min(self.watches.len().saturating_sub(1));
+            }
+            KeyCode::Char('R') => self.edit_captures(|app| {
+                for watch in &mut app.watches {
+                    watch.reset();
+                }
+            }),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Opens the add-watch prompt, pre-filled with the field under the cursor
+    /// or the selected message.
+    fn open_watch_prompt(&mut self) {
+        let text = match self.selected_entry() {
+            Some(entry) => match entry.fields.get(self.field_selected) {
+                Some(row) if self.focus == Focus::Detail => format!(
+                    "{}:{} {}.{}",
+                    entry.sys_id, entry.comp_id, entry.name, row.path
+                ),
+                _ => format!("{}.", entry.name),
+            },
+            None => String::new(),
+        };
+        self.watch_input = Some(text);
+        self.watch_error = None;
+    }
+
+    /// Adds a watch and seeds it with the latest matching message.
+    pub fn add_watch(&mut self, spec: &str) {
+        let watch = match Watch::parse(spec) {
+            Ok(watch) => watch,
+            Err(e) => {
+                self.watch_error = Some(e.to_string());
+                return;
+            }
+        };
+        self.watch_input = None;
+        self.watch_error = None;
+        if self.panel != Some(Panel::Watch) {
+            self.toggle_panel(Panel::Watch);
+        }
+        if let Some(pos) = self.watches.iter().position(|w| w.label() == watch.label()) {
+            self.watch_selected = pos;
+            return;
+        }
+        self.edit_captures(|app| app.push_watch(watch.clone()));
+    }
+
+    fn push_watch(&mut self, mut watch: Watch) {
+        let latest = self
+            .entries
+            .iter_mut()
+            .filter(|e| e.name == watch.message)
+            .filter(|e| watch.source.is_none_or(|s| s == (e.sys_id, e.comp_id)))
+            .max_by_key(|e| e.last_received);
+        if let Some(entry) = latest {
+            entry.update_fields();
+            watch.update(
+                (entry.sys_id, entry.comp_id),
+                &entry.name,
+                &entry.fields,
+                entry.last_received,
+            );
+        }
+        self.watches.push(watch);
+    }
+
+    /// Pins the numeric field under the cursor to the plot panel, or unpins it.
+    fn toggle_pin(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let Some(row) = entry.fields.get(self.field_selected) else {
+            return;
+        };
+        if row.value.as_f64().is_none() {
+            return;
+        }
+
+        let pin = PinnedField {
+            link: entry.link,
+            sys_id: entry.sys_id,
+            comp_id: entry.comp_id,
+            message: entry.name.clone(),
+            path: row.path.clone(),
+            samples: VecDeque::new(),
+        };
+        self.edit_captures(
+            |app| match app.pinned.iter().position(|p| p.same_field(&pin)) {
+                Some(pos) => {
+                    app.pinned.remove(pos);
+                }
+                None => app.pinned.push(pin.clone()),
+            },
+        );
+    }
+
+    /// The instance shown in the detail pane, as plain text and as JSON.
+    fn message_snapshot(&self) -> Option<(String, MessageSnapshot)> {
+        let entry = self.selected_entry()?;
+        let past = entry
+            .history
+            .len()
+            .checked_sub(self.history_offset + 1)
+            .filter(|_| self.history_offset > 0)
+            .map(|idx| &entry.history[idx]);
+        let (message, time) = match past {
+            Some(item) => (&item.message, item.time),
+            None => (
+                entry.last_message(),
+                entry.history.back().map_or_else(Utc::now, |item| item.time),
+            ),
+        };
+        let time = time.with_timezone(&Local);
+        let link = self.multi_link().then(|| entry.via.clone());
+
+        let mut text = format!("{} [{}:{}]", entry.name, entry.sys_id, entry.comp_id);
+        if let Some(link) = &link {
+            text.push_str(&format!(" via {}", link));
+        }
+        text.push_str(&format!(" at {}\n", time.format("%Y-%m-%d %H:%M:%S%.3f")));
+        let humanize_as = self.humanize.then_some(entry.name.as_str());
+        text.push_str(&share::fields_text(
+            &fields::field_rows(message),
+            humanize_as,
+        ));
+
+        let snapshot = MessageSnapshot {
+            time,
+            system_id: entry.sys_id,
+            component_id: entry.comp_id,
+            message_id: message.message_id(),
+            message_name: entry.name.clone(),
+            link,
+            fields: share::fields_json(&fields::message_fields(message)),
+        };
+        Some((text, snapshot))
+    }
+
+    fn copy_message(&mut self) {
+        let Some((text, snapshot)) = self.message_snapshot() else {
+            return;
+        };
+        self.status = Some(match share::copy_to_clipboard(&text) {
+            Ok(()) => format!(
+                "Copied {} [{}:{}] to the clipboard",
+                snapshot.message_name, snapshot.system_id, snapshot.component_id
+            ),
+            Err(e) => format!("Copy failed: {}", e),
+        });
+    }
+
+    fn save_message(&mut self, prompt: SavePrompt) {
+        let path = prompt.path;
+        self.status = Some(match share::save(&path, &prompt.text, &prompt.snapshot) {
+            Ok(()) => format!("Saved {} to {}", prompt.snapshot.message_name, path),
+            Err(e) => format!("Save to {} failed: {}", path, e),
+        });
+    }
+
+    /// Writes every entry with its counts, rates and latest contents to a
+    /// timestamped JSON file in the working directory.
+    fn write_table_snapshot(&mut self) {
+        let multi_link = self.multi_link();
+        let mut entries: Vec<EntrySnapshot> = self
+            .entries
+            .iter()
+            .map(|entry| EntrySnapshot {
+                system_id: entry.sys_id,
+                component_id: entry.comp_id,
+                message_name: entry.name.clone(),
+                link: multi_link.then(|| entry.via.clone()),
+                count: entry.count,
+                hz: entry.hz,
+                bytes_per_sec: entry.bytes_per_sec,
+                total_bytes: entry.total_bytes,
+                age_secs: entry.age,
+                fields: share::fields_json(&fields::message_fields(entry.last_message())),
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            (a.system_id, a.component_id, &a.message_name, &a.link).cmp(&(
+                b.system_id,
+                b.component_id,
+                &b.message_name,
+                &b.link,
+            ))
+        });
+        let time = Local::now();
+        let snapshot = TableSnapshot {
+            time,
+            connections: self
+                .connections
+                .iter()
+                .map(|c| ConnectionSnapshot {
+                    name: c.name.clone(),
+                    uri: c.uri.clone(),
+                })
+                .collect(),
+            total_messages: self.total_count,
+            entries,
+        };
+        let path = format!("mavshark-snapshot-{}.json", time.format("%Y%m%d-%H%M%S"));
+        self.status = Some(match share::write_json(&path, &snapshot) {
+            Ok(()) => format!("Wrote {} entries to {}", snapshot.entries.len(), path),
+            Err(e) => format!("Snapshot to {} failed: {}", path, e),
+        });
+    }
+
+    /// Moves the history view `steps` instances back (negative: forward).
+    fn step_history(&mut self, steps: isize) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let oldest = entry.history.len().saturating_sub(1);
+        self.history_offset = self.history_offset.saturating_add_signed(steps).min(oldest);
+    }
+
+    /// Collapses (`Some(true)`), expands (`Some(false)`) or toggles the
+    /// selected tree node.
+    fn toggle_collapsed(&mut self, collapse: Option<bool>) {
+        let node = match self.rows.get(self.selected) {
+            Some(&TableRow::System { sys_id, .. }) => (sys_id, None),
+            Some(&TableRow::Component {
+                sys_id, comp_id, ..
+            }) => (sys_id, Some(comp_id)),
+            _ => return,
+        };
+        let collapse = collapse.unwrap_or(!self.collapsed.contains(&node));
+        if collapse {
+            self.collapsed.insert(node);
+        } else {
+            self.collapsed.remove(&node);
+        }
+        self.rebuild_filter();
+    }
+
+    /// Flips a hide/pin flag on the selected entry and persists it.
+    fn toggle_saved(
+        &mut self,
+        flag: fn(&mut MessageEntry) -> &mut bool,
+        set: fn(&mut SavedState) -> &mut BTreeSet<EntryKey>,
+    ) {
+        let Some(idx) = self.selected_index() else {
+            return;
+        };
+        let entry = &mut self.entries[idx];
+        let key = entry.key();
+        let value = !*flag(entry);
+        self.edit_captures(|app| *flag(&mut app.entries[idx]) = value);
+        if value {
+            set(&mut self.saved).insert(key);
+        } else {
+            set(&mut self.saved).remove(&key);
+        }
+        self.save_state();
+    }
+
+    fn save_state(&mut self) {
+        if let Err(e) = self.saved.save() {
+            log::warn!("Failed to save hidden/pinned entries: {:#}", e);
+        }
+        self.rebuild_filter();
+    }
+
+    /// Whether more than one connection is open, so tables need a link column.
+    pub fn multi_link(&self) -> bool {
+        self.connections.len() > 1
+    }
+
+    pub fn hidden_count(&self) -> usize {
+        self.entries.iter().filter(|e| e.hidden).count()
+    }
+
+    fn toggle_panel(&mut self, panel: Panel) {
+        self.panel = if self.panel == Some(panel) {
+            None
+        } else {
+            Some(panel)
+        };
+        let focused_panel = match self.focus {
+            Focus::Console => Some(Panel::Console),
+            Focus::Watch => Some(Panel::Watch),
+            Focus::Table | Focus::Detail => None,
+        };
+        if focused_panel.is_some() && focused_panel != self.panel {
+            self.focus = Focus::Table;
+        }
+    }
+
+    /// Loss across all links, weighted by the number of expected packets.
+    pub fn total_loss_percent(&self) -> Option<f64> {
+        let (lost, expected) = self.links.values().fold((0, 0), |(lost, expected), s| {
+            (lost + s.lost, expected + s.received - s.duplicated + s.lost)
+        });
+        (expected > 0).then(|| lost as f64 / expected as f64 * 100.0)
+    }
+
+    pub fn is_pinned(&self, entry: &MessageEntry, path: &str) -> bool {
+        self.pinned
+            .iter()
+            .any(|p| p.matches(entry) && p.path == path)
+    }
+
+    fn parse_filter(&mut self) {
+        match Expr::parse(&self.filter) {
+            Ok(expr) => {
+                self.filter_expr = expr;
+                self.filter_error = None;
+            }
+            Err(e) => self.filter_error = Some(e.to_string()),
+        }
+        self.rebuild_filter();
     }
 
     fn rebuild_filter(&mut self) {
-        let filter_upper = self.filter.to_uppercase();
         self.filtered_indices = self
             .entries
             .iter()
             .enumerate()
+            .filter(|(_, e)| !e.hidden)
             .filter(|(_, e)| {
-                if self.filter.is_empty() {
-                    true
-                } else {
-                    e.name.to_uppercase().contains(&filter_upper)
-                        || format!("{}:{}", e.sys_id, e.comp_id).contains(&self.filter)
-                }
+                self.filter_expr
+                    .as_ref()
+                    .is_none_or(|expr| expr.matches(*e))
             })
             .map(|(i, _)| i)
             .collect();
@@ -213,20 +1563,96 @@ impl App {
             }
         }
 
-        if self.selected >= self.filtered_indices.len() {
-            self.selected = self.filtered_indices.len().saturating_sub(1);
+        // Stable, so pinned entries keep the sort order among themselves
+        self.filtered_indices
+            .sort_by_key(|&i| !self.entries[i].pinned_top);
+
+        self.rows = if self.tree_view {
+            self.tree_rows()
+        } else {
+            self.filtered_indices
+                .iter()
+                .map(|&i| TableRow::Message(i))
+                .collect()
+        };
+
+        if self.selected >= self.rows.len() {
+            self.selected = self.rows.len().saturating_sub(1);
         }
-        if self.filtered_indices.is_empty() {
+        if self.rows.is_empty() {
             self.table_state.select(None);
         } else {
             self.table_state.select(Some(self.selected));
         }
+        self.dirty = false;
+    }
+
+    /// The time the view is drawn at: now, or the moment it was paused.
+    pub fn view_time(&self) -> Instant {
+        self.paused_at.unwrap_or_else(Instant::now)
+    }
+
+    /// Messages received since the view was paused.
+    pub fn pending_messages(&self) -> u64 {
+        self.live
+            .as_ref()
+            .map_or(0, |live| live.total_count - self.total_count)
+    }
+
+    /// Groups the filtered entries by system and component, keeping the
+    /// sort order within each component.
+    fn tree_rows(&self) -> Vec<TableRow> {
+        let mut systems: BTreeMap<u8, BTreeMap<u8, Vec<usize>>> = BTreeMap::new();
+        for &idx in &self.filtered_indices {
+            let entry = &self.entries[idx];
+            systems
+                .entry(entry.sys_id)
+                .or_default()
+                .entry(entry.comp_id)
+                .or_default()
+                .push(idx);
+        }
+
+        let totals = |indices: &[usize]| {
+            indices.iter().fold((0.0, 0), |(hz, count), &i| {
+                (hz + self.entries[i].hz, count + self.entries[i].count)
+            })
+        };
+
+        let mut rows = Vec::new();
+        for (sys_id, components) in systems {
+            let all: Vec<usize> = components.values().flatten().copied().collect();
+            let (hz, count) = totals(&all);
+            rows.push(TableRow::System { sys_id, hz, count });
+            if self.collapsed.contains(&(sys_id, None)) {
+                continue;
+            }
+            for (comp_id, indices) in components {
+                let (hz, count) = totals(&indices);
+                rows.push(TableRow::Component {
+                    sys_id,
+                    comp_id,
+                    hz,
+                    count,
+                });
+                if !self.collapsed.contains(&(sys_id, Some(comp_id))) {
+                    rows.extend(indices.into_iter().map(TableRow::Message));
+                }
+            }
+        }
+        rows
+    }
+
+    /// Index into `entries` of the selected row, if it is a message.
+    pub fn selected_index(&self) -> Option<usize> {
+        match self.rows.get(self.selected) {
+            Some(&TableRow::Message(idx)) => Some(idx),
+            _ => None,
+        }
     }
 
     pub fn selected_entry(&self) -> Option<&MessageEntry> {
-        self.filtered_indices
-            .get(self.selected)
-            .and_then(|&idx| self.entries.get(idx))
+        self.selected_index().and_then(|idx| self.entries.get(idx))
     }
 
     pub fn sort_label(&self) -> &str {
@@ -237,3 +1663,219 @@ impl App {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use mavlink::ardupilotmega::{HEARTBEAT_DATA, STATUSTEXT_DATA};
+    use mavlink::MavHeader;
+
+    use super::*;
+    use crate::alert::AlertRule;
+    use crate::framing::TextChunk;
+
+    fn heartbeat(sys_id: u8) -> LinkEvent {
+        LinkEvent::Message(ReceivedMessage {
+            link: 0,
+            header: MavHeader {
+                system_id: sys_id,
+                component_id: 1,
+                sequence: 0,
+            },
+            message: MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+            received_at: Instant::now(),
+            timestamp: Utc::now(),
+            frame_len: 21,
+            text_chunk: None,
+        })
+    }
+
+    fn statustext(link: usize, text: &str) -> LinkEvent {
+        let mut status = STATUSTEXT_DATA::default();
+        status.text[..text.len()].copy_from_slice(text.as_bytes());
+        LinkEvent::Message(ReceivedMessage {
+            link,
+            header: MavHeader {
+                system_id: 1,
+                component_id: 1,
+                sequence: 0,
+            },
+            message: MavMessage::STATUSTEXT(status),
+            received_at: Instant::now(),
+            timestamp: Utc::now(),
+            frame_len: 63,
+            text_chunk: Some(TextChunk::default()),
+        })
+    }
+
+    fn app_with_rule(rule: &str) -> App {
+        let mut app = App::new(vec![Connection::parse("udpin:0.0.0.0:14550")], None);
+        app.alerts.rules = vec![AlertRule::parse(rule).unwrap()];
+        app.alerts.hold_secs = 0.0;
+        app
+    }
+
+    #[test]
+    fn alerts_follow_the_link_while_paused() {
+        let mut app = app_with_rule("HEARTBEAT and count > 1");
+        app.on_event(heartbeat(1));
+        app.tick();
+        app.toggle_pause();
+        app.on_event(heartbeat(1));
+        app.on_event(heartbeat(2));
+        app.on_event(heartbeat(2));
+        app.tick();
+        assert_eq!(app.entries.len(), 1, "the view stays frozen");
+        assert_eq!(app.alerts.active_count(), 2);
+        assert_eq!(app.alerts.take_new().len(), 2);
+
+        // Resuming shows the capture the alerts followed, so nothing fires twice
+        app.toggle_pause();
+        app.tick();
+        assert_eq!(app.entries.len(), 2);
+        assert_eq!(app.alerts.active_count(), 2);
+        assert!(app.alerts.take_new().is_empty());
+    }
+
+    #[test]
+    fn messages_received_while_paused_keep_their_arrival_time() {
+        let mut app = App::new(Vec::new(), None);
+        let arrived = Utc::now() - chrono::Duration::seconds(60);
+        app.toggle_pause();
+        for mut event in [heartbeat(1), statustext(0, "Arming")] {
+            if let LinkEvent::Message(msg) = &mut event {
+                msg.timestamp = arrived;
+            }
+            app.on_event(event);
+        }
+        app.toggle_pause();
+        assert_eq!(app.entries[0].history[0].time, arrived);
+        assert_eq!(app.console.lines[0].time, arrived.with_timezone(&Local));
+    }
+
+    #[test]
+    fn the_view_stays_frozen_while_the_capture_goes_on() {
+        let mut app = App::new(Vec::new(), None);
+        app.on_event(heartbeat(1));
+        app.add_watch("HEARTBEAT.mavlink_version");
+        app.toggle_pause();
+        app.on_event(heartbeat(1));
+        app.on_event(heartbeat(2));
+        assert_eq!(app.entries.len(), 1);
+        assert_eq!(app.entries[0].count, 1);
+        assert_eq!(app.pending_messages(), 2);
+
+        // Edits made while paused outlast the pause
+        app.refresh();
+        app.toggle_pin();
+        app.watch_selected = 0;
+        app.on_watch_key(KeyEvent::from(KeyCode::Char('x')));
+        assert!(app.watches.is_empty());
+
+        app.toggle_pause();
+        assert_eq!(app.pending_messages(), 0);
+        assert_eq!(app.entries.len(), 2);
+        assert_eq!(app.entries[0].count, 2);
+        assert_eq!(app.pinned.len(), 1);
+        assert!(app.watches.is_empty());
+    }
+
+    #[test]
+    fn only_messages_on_screen_are_compared() {
+        let mut app = App::new(Vec::new(), None);
+        app.on_event(heartbeat(1));
+        app.on_event(heartbeat(2));
+        app.refresh();
+        let shown = app.selected_index().unwrap();
+        let other = 1 - shown;
+        app.entries[other].update_fields();
+
+        app.on_event(heartbeat(1));
+        app.on_event(heartbeat(2));
+        assert!(!app.entries[shown].fields_stale, "nothing changed");
+        assert!(app.entries[other].fields_stale, "decoded again once shown");
+    }
+
+    #[test]
+    fn console_keeps_texts_when_a_connection_drops() {
+        let mut app = App::new(Vec::new(), None);
+        app.on_event(statustext(0, "Arming"));
+        app.on_event(statustext(1, "Arming"));
+        assert_eq!(app.console.lines.len(), 1, "one text over two connections");
+        // Connection 0 is gone; connection 1 still reaches the console
+        app.on_event(statustext(1, "Disarmed"));
+        app.on_event(statustext(1, "Landed"));
+        assert_eq!(app.console.lines.len(), 3);
+    }
+
+    #[test]
+    fn header_clicks_follow_the_drawn_columns() {
+        use ratatui::backend::TestBackend;
+        use ratatui::Terminal;
+
+        let mut app = App::new(
+            vec![
+                Connection::parse("udpin:0.0.0.0:14550"),
+                Connection::parse("udpin:0.0.0.0:14551"),
+            ],
+            None,
+        );
+        app.on_event(heartbeat(1));
+        app.tick();
+        app.refresh();
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal.draw(|f| crate::ui::draw(f, &mut app)).unwrap();
+
+        let header_y = app.hit.table.y + 1;
+        let buffer = terminal.backend().buffer().clone();
+        let column_of = |title: &str| {
+            let line: String = (0..buffer.area.width)
+                .map(|x| buffer[(x, header_y)].symbol().to_string())
+                .collect();
+            let start = line.find(title).unwrap();
+            line[..start].chars().count() as u16
+        };
+        let click = |app: &mut App, column: u16| {
+            app.on_mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column,
+                row: header_y,
+                modifiers: KeyModifiers::NONE,
+            });
+        };
+
+        click(&mut app, column_of("Hz "));
+        assert!(app.sort_mode == SortMode::Hz);
+        click(&mut app, column_of("Count"));
+        assert!(app.sort_mode == SortMode::Count);
+        // The Link column does not sort
+        click(&mut app, column_of("Link"));
+        assert!(app.sort_mode == SortMode::Count);
+        click(&mut app, column_of("Message"));
+        assert!(app.sort_mode == SortMode::Alphabetical);
+    }
+
+    #[test]
+    fn ticks_only_rebuild_the_table_when_it_can_change() {
+        let mut app = App::new(Vec::new(), None);
+        app.on_event(heartbeat(1));
+        app.tick();
+        app.refresh();
+        app.tick();
+        assert!(!app.dirty, "nothing arrived or expired");
+
+        app.sort_mode = SortMode::Hz;
+        app.hz_window_secs = 1e-9;
+        app.tick();
+        assert!(app.dirty, "the rate dropped while sorting by it");
+
+        app.sort_mode = SortMode::Alphabetical;
+        app.refresh();
+        app.tick();
+        assert!(!app.dirty);
+        app.filter = "age > 10".to_string();
+        app.parse_filter();
+        app.refresh();
+        app.tick();
+        assert!(app.dirty, "age filters follow the clock");
+    }
+}
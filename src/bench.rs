@@ -0,0 +1,214 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use mavlink::ardupilotmega::*;
+use mavlink::MavHeader;
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+
+use crate::app::App;
+use crate::link::Connection;
+use crate::mavlink_io::{self, LinkEvent, ReceivedMessage};
+use crate::ui;
+
+/// A frame taking longer than this is a visible stall
+const STALL: Duration = Duration::from_millis(100);
+/// Most traffic, in seconds, allowed to queue up before the view counts as behind
+const MAX_LAG_SECS: f64 = 0.25;
+
+/// Runs the live view's ingest, tick and draw loop against an off-screen
+/// terminal while a thread sends `rate` synthetic messages per second from
+/// `vehicles` simulated vehicles, then reports how well it kept up.
+pub fn run_bench(rate: u32, secs: f64, vehicles: u8) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let sent = Arc::new(AtomicU64::new(0));
+    let producer = {
+        let sent = sent.clone();
+        thread::spawn(move || produce(tx, rate, secs, vehicles.max(1), &sent))
+    };
+
+    let mut app = App::new(vec![Connection::parse("bench")], None);
+    // A watched stream and the selected row are decoded and compared as they arrive
+    app.add_watch("ATTITUDE.roll");
+    let mut terminal = Terminal::new(TestBackend::new(160, 48))?;
+    let mut frame_times = Vec::new();
+    let mut max_backlog = 0;
+    let start = Instant::now();
+    loop {
+        let frame_start = Instant::now();
+        let taken = crate::ingest(&mut app, &rx, &mut None);
+        app.tick();
+        app.refresh();
+        terminal.draw(|f| ui::draw(f, &mut app))?;
+        frame_times.push(frame_start.elapsed());
+
+        let backlog = sent.load(Ordering::Relaxed).saturating_sub(app.total_count);
+        max_backlog = max_backlog.max(backlog);
+        if taken == 0 && producer.is_finished() {
+            break;
+        }
+        thread::sleep(crate::POLL_INTERVAL);
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    let _ = producer.join();
+
+    let sent = sent.load(Ordering::Relaxed);
+    let max_frame = frame_times.iter().max().copied().unwrap_or_default();
+    let mean_frame = frame_times.iter().sum::<Duration>() / frame_times.len().max(1) as u32;
+    let stalls = frame_times.iter().filter(|&&t| t > STALL).count();
+    println!(
+        "Sent      {} msgs over {:.1} s ({} msgs/s, {} vehicles, {} streams)",
+        sent,
+        secs,
+        rate,
+        vehicles,
+        app.entries.len()
+    );
+    println!(
+        "Ingested  {} msgs in {:.1} s ({:.0} msgs/s)",
+        app.total_count,
+        elapsed,
+        app.total_count as f64 / elapsed
+    );
+    println!(
+        "Frames    {}, mean {:.1} ms, max {:.1} ms, {} over {} ms",
+        frame_times.len(),
+        mean_frame.as_secs_f64() * 1000.0,
+        max_frame.as_secs_f64() * 1000.0,
+        stalls,
+        STALL.as_millis()
+    );
+    let lag_secs = max_backlog as f64 / f64::from(rate.max(1));
+    println!(
+        "Backlog   max {} msgs ({:.2} s of traffic)",
+        max_backlog, lag_secs
+    );
+    if stalls > 0 || lag_secs > MAX_LAG_SECS {
+        bail!("The view fell behind");
+    }
+    println!("OK: kept up without stalls");
+    Ok(())
+}
+
+/// Sends a steady `rate` messages per second, cycling through every stream
+/// of every vehicle with values that change on each message.
+fn produce(tx: Sender<LinkEvent>, rate: u32, secs: f64, vehicles: u8, sent: &AtomicU64) {
+    let start = Instant::now();
+    let total = (f64::from(rate) * secs) as u64;
+    let mut n: u64 = 0;
+    let mut sequences = vec![0u8; usize::from(vehicles)];
+    while n < total {
+        let due = ((start.elapsed().as_secs_f64() * f64::from(rate)) as u64).min(total);
+        while n < due {
+            let vehicle = (n % u64::from(vehicles)) as u8;
+            let step = n / u64::from(vehicles);
+            let sequence = &mut sequences[usize::from(vehicle)];
+            *sequence = sequence.wrapping_add(1);
+            let message = synthetic_message(step);
+            let msg = ReceivedMessage {
+                link: 0,
+                header: MavHeader {
+                    system_id: vehicle + 1,
+                    component_id: 1,
+                    sequence: *sequence,
+                },
+                frame_len: mavlink_io::frame_len(&message),
+                message,
+                received_at: Instant::now(),
+                timestamp: Utc::now(),
+                text_chunk: None,
+            };
+            if tx.send(LinkEvent::Message(msg)).is_err() {
+                return;
+            }
+            n += 1;
+            sent.store(n, Ordering::Relaxed);
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// The `step`-th message of a vehicle: a dozen common streams in turn.
+fn synthetic_message(step: u64) -> MavMessage {
+    let t = step as u32;
+    let x = (step as f32 * 0.01).sin();
+    match step % 12 {
+        0 => MavMessage::HEARTBEAT(HEARTBEAT_DATA {
+            custom_mode: t / 1000 % 4,
+            mavtype: MavType::MAV_TYPE_QUADROTOR,
+            autopilot: MavAutopilot::MAV_AUTOPILOT_ARDUPILOTMEGA,
+            ..Default::default()
+        }),
+        1 => MavMessage::SYS_STATUS(SYS_STATUS_DATA {
+            voltage_battery: 12_000 + (t % 500) as u16,
+            battery_remaining: 80,
+            ..Default::default()
+        }),
+        2 => MavMessage::GLOBAL_POSITION_INT(GLOBAL_POSITION_INT_DATA {
+            time_boot_ms: t,
+            lat: 520_000_000 + t as i32,
+            lon: 40_000_000 - t as i32,
+            alt: 10_000,
+            ..Default::default()
+        }),
+        3 => MavMessage::GPS_RAW_INT(GPS_RAW_INT_DATA {
+            time_usec: u64::from(t) * 1000,
+            lat: 520_000_000 + t as i32,
+            lon: 40_000_000 - t as i32,
+            satellites_visible: 12,
+            ..Default::default()
+        }),
+        4 => MavMessage::RAW_IMU(RAW_IMU_DATA {
+            time_usec: u64::from(t) * 1000,
+            xacc: (x * 1000.0) as i16,
+            zacc: -1000,
+            ..Default::default()
+        }),
+        5 => MavMessage::SCALED_IMU2(SCALED_IMU2_DATA {
+            time_boot_ms: t,
+            xgyro: (x * 100.0) as i16,
+            ..Default::default()
+        }),
+        6 => MavMessage::SERVO_OUTPUT_RAW(SERVO_OUTPUT_RAW_DATA {
+            time_usec: t,
+            servo1_raw: 1500 + (x * 400.0) as u16,
+            servo2_raw: 1500,
+            ..Default::default()
+        }),
+        7 => MavMessage::RC_CHANNELS(RC_CHANNELS_DATA {
+            time_boot_ms: t,
+            chan1_raw: 1500 + (x * 400.0) as u16,
+            chancount: 8,
+            ..Default::default()
+        }),
+        8 => MavMessage::VFR_HUD(VFR_HUD_DATA {
+            groundspeed: x.abs() * 10.0,
+            alt: 100.0 + x,
+            heading: (t % 360) as i16,
+            ..Default::default()
+        }),
+        9 => MavMessage::LOCAL_POSITION_NED(LOCAL_POSITION_NED_DATA {
+            time_boot_ms: t,
+            x,
+            y: -x,
+            ..Default::default()
+        }),
+        10 => MavMessage::SCALED_PRESSURE(SCALED_PRESSURE_DATA {
+            time_boot_ms: t,
+            press_abs: 1013.0 + x,
+            ..Default::default()
+        }),
+        _ => MavMessage::ATTITUDE(ATTITUDE_DATA {
+            time_boot_ms: t,
+            roll: x,
+            pitch: -x,
+            yaw: x * 2.0,
+            ..Default::default()
+        }),
+    }
+}
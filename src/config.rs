@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::app::{Panel, SortMode, MAX_PLOT_WINDOW_SECS, MIN_PLOT_WINDOW_SECS};
+use crate::mouse::{MAX_SPLIT_PERCENT, MIN_SPLIT_PERCENT};
+use crate::state;
+use crate::theme::Theme;
+
+/// Settings from `config.toml` in the config directory. Every field is
+/// optional; command-line flags take precedence over anything set here.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub defaults: Defaults,
+    pub profiles: BTreeMap<String, Profile>,
+    pub keys: Keys,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Defaults {
+    /// `name`, `hz` or `count`
+    pub sort: Option<String>,
+    /// Seconds of traffic the Hz column averages over
+    pub hz_window: Option<f64>,
+    /// Width of the message table in percent of the screen
+    pub split: Option<u16>,
+    pub tree: Option<bool>,
+    /// Panel open at startup
+    pub panel: Option<String>,
+    /// `dark`, `light`, `high-contrast` or `mono`
+    pub theme: Option<String>,
+    pub humanize: Option<bool>,
+    /// Count a message once across connections, see `--merge-links`
+    pub merge_links: Option<bool>,
+    pub mouse: Option<bool>,
+    pub plot_window: Option<f64>,
+    pub history: Option<usize>,
+    pub log_file: Option<String>,
+}
+
+/// A named connection, selected with `--profile`.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub uri: Option<String>,
+    /// More connections, opened alongside `uri`
+    pub uris: Vec<String>,
+    pub heartbeat_sys_id: Option<u8>,
+    pub heartbeat_comp_id: Option<u8>,
+    pub record: Option<String>,
+    pub record_filter: Option<String>,
+    pub alerts: Vec<String>,
+    pub watches: Vec<String>,
+}
+
+/// Action name to key, for the live and the replay view.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Keys {
+    pub live: BTreeMap<String, String>,
+    pub replay: BTreeMap<String, String>,
+}
+
+fn config_path() -> Option<PathBuf> {
+    state::config_dir().map(|dir| dir.join("config.toml"))
+}
+
+impl Config {
+    /// Reads `path`, or `config.toml` in the config directory if none is
+    /// given. Only a missing default file is not an error.
+    pub fn load(path: Option<&str>) -> Result<Self> {
+        let (path, required) = match path {
+            Some(path) => (PathBuf::from(path), true),
+            None => match config_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if !required && e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default())
+            }
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        let config: Self =
+            toml::from_str(&text).with_context(|| format!("Invalid {}", path.display()))?;
+        config
+            .check()
+            .with_context(|| format!("Invalid {}", path.display()))?;
+        Ok(config)
+    }
+
+    /// Validates the values serde cannot check by type.
+    fn check(&self) -> Result<()> {
+        self.defaults.sort_mode()?;
+        self.defaults.panel()?;
+        if let Some(theme) = &self.defaults.theme {
+            Theme::named(theme)?;
+        }
+        if let Some(split) = self.defaults.split {
+            if !(MIN_SPLIT_PERCENT..=MAX_SPLIT_PERCENT).contains(&split) {
+                anyhow::bail!(
+                    "split must be between {} and {}",
+                    MIN_SPLIT_PERCENT,
+                    MAX_SPLIT_PERCENT
+                );
+            }
+        }
+        if self.defaults.hz_window.is_some_and(|w| w <= 0.0) {
+            anyhow::bail!("hz_window must be positive");
+        }
+        if let Some(secs) = self.defaults.plot_window {
+            check_plot_window(secs)?;
+        }
+        Ok(())
+    }
+
+    pub fn profile(&self, name: &str) -> Result<&Profile> {
+        self.profiles.get(name).with_context(|| {
+            let names: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            if names.is_empty() {
+                format!("Unknown profile '{}' (no profiles configured)", name)
+            } else {
+                format!(
+                    "Unknown profile '{}' (available: {})",
+                    name,
+                    names.join(", ")
+                )
+            }
+        })
+    }
+}
+
+impl Defaults {
+    pub fn sort_mode(&self) -> Result<Option<SortMode>> {
+        let Some(sort) = &self.sort else {
+            return Ok(None);
+        };
+        Ok(Some(match sort.to_lowercase().as_str() {
+            "name" | "a-z" => SortMode::Alphabetical,
+            "hz" => SortMode::Hz,
+            "count" => SortMode::Count,
+            _ => anyhow::bail!("Unknown sort '{}' (expected name, hz or count)", sort),
+        }))
+    }
+
+    pub fn panel(&self) -> Result<Option<Panel>> {
+        let Some(panel) = &self.panel else {
+            return Ok(None);
+        };
+        Ok(Some(match panel.to_lowercase().as_str() {
+            "links" => Panel::Links,
+            "throughput" => Panel::Throughput,
+            "vehicles" => Panel::Vehicles,
+            "console" => Panel::Console,
+            "alerts" => Panel::Alerts,
+            "watch" => Panel::Watch,
+            _ => anyhow::bail!(
+                "Unknown panel '{}' (expected links, throughput, vehicles, console, alerts or watch)",
+                panel
+            ),
+        }))
+    }
+}
+
+/// Checks a plot window from `[defaults]` or `--plot-window`.
+pub fn check_plot_window(secs: f64) -> Result<()> {
+    if !(MIN_PLOT_WINDOW_SECS..=MAX_PLOT_WINDOW_SECS).contains(&secs) {
+        anyhow::bail!(
+            "plot_window must be between {} and {} seconds",
+            MIN_PLOT_WINDOW_SECS,
+            MAX_PLOT_WINDOW_SECS
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,477 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::Instant;
+
+use chrono::{DateTime, Local};
+use mavlink::ardupilotmega::{MavMessage, MavSeverity, STATUSTEXT_DATA};
+
+use crate::framing::TextChunk;
+use crate::mavlink_io::ReceivedMessage;
+
+/// A chunked text still missing pieces after this long is left as it is, and
+/// a later chunk with its id starts a new line.
+const CHUNK_TIMEOUT_SECS: f64 = 5.0;
+/// Oldest lines are dropped beyond this many
+const MAX_LINES: usize = 10_000;
+/// How long a STATUSTEXT heard on one connection hides the same one arriving
+/// on another
+const DUPLICATE_SECS: f64 = 2.0;
+
+#[derive(Clone)]
+pub struct StatusLine {
+    pub time: DateTime<Local>,
+    pub sys_id: u8,
+    pub comp_id: u8,
+    pub severity: MavSeverity,
+    pub text: String,
+}
+
+/// A chunked text being put back together.
+#[derive(Clone)]
+struct OpenText {
+    /// Index into `lines`
+    line: usize,
+    chunks: BTreeMap<u8, String>,
+    /// `chunk_seq` of the chunk that ended the text, once it arrived
+    last_seq: Option<u8>,
+    updated: Instant,
+}
+
+impl OpenText {
+    fn is_complete(&self) -> bool {
+        self.last_seq
+            .is_some_and(|last| self.chunks.len() == usize::from(last) + 1)
+    }
+}
+
+/// A STATUSTEXT as it arrived, to recognize the same one on another connection.
+#[derive(Clone)]
+struct Heard {
+    at: Instant,
+    link: usize,
+    sender: (u8, u8),
+    chunk: TextChunk,
+    severity: MavSeverity,
+    text: [u8; 50],
+}
+
+/// STATUSTEXT history with its own severity filter and search.
+///
+/// Texts longer than 50 characters arrive as several STATUSTEXTs sharing a
+/// non-zero `id`, numbered by `chunk_seq`; the last one is shorter than 50
+/// bytes (or null-terminated). Chunks are joined in `chunk_seq` order per
+/// sender and id, whatever else arrives in between. Id 0 is a complete text.
+/// With several connections, a text is shown once however many deliver it.
+#[derive(Clone)]
+pub struct Console {
+    pub lines: VecDeque<StatusLine>,
+    /// Texts received in the last `DUPLICATE_SECS`
+    heard: VecDeque<Heard>,
+    /// Texts still waiting for chunks, by sys, comp and id
+    open: HashMap<(u8, u8, u16), OpenText>,
+    /// Highest severity value shown (0 = EMERGENCY .. 7 = DEBUG)
+    pub max_severity: u8,
+    pub search: String,
+    pub search_active: bool,
+    /// Lines scrolled up from the newest; 0 follows new output
+    pub scroll: usize,
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Self {
+            lines: VecDeque::new(),
+            heard: VecDeque::new(),
+            open: HashMap::new(),
+            max_severity: MavSeverity::MAV_SEVERITY_DEBUG as u8,
+            search: String::new(),
+            search_active: false,
+            scroll: 0,
+        }
+    }
+}
+
+impl Console {
+    /// Adds the text of a STATUSTEXT, stamped with its arrival time; other
+    /// messages are ignored.
+    pub fn push(&mut self, msg: &ReceivedMessage) {
+        let MavMessage::STATUSTEXT(status) = &msg.message else {
+            return;
+        };
+        let (sys_id, comp_id) = (msg.header.system_id, msg.header.component_id);
+        let chunk = msg.text_chunk.unwrap_or_default();
+        let at = msg.received_at;
+        let time = msg.timestamp.with_timezone(&Local);
+        if self.is_duplicate(msg.link, (sys_id, comp_id), status, chunk, at) {
+            return;
+        }
+        let len = status
+            .text
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(status.text.len());
+        let text = String::from_utf8_lossy(&status.text[..len]).into_owned();
+        let last = len < status.text.len();
+
+        self.open
+            .retain(|_, open| at.duration_since(open.updated).as_secs_f64() < CHUNK_TIMEOUT_SECS);
+        if chunk.id == 0 {
+            self.push_line(time, sys_id, comp_id, status.severity, text);
+            return;
+        }
+
+        let key = (sys_id, comp_id, chunk.id);
+        let open = match self.open.get_mut(&key) {
+            Some(open) => open,
+            None => {
+                let line = self.push_line(time, sys_id, comp_id, status.severity, String::new());
+                self.open.entry(key).or_insert(OpenText {
+                    line,
+                    chunks: BTreeMap::new(),
+                    last_seq: None,
+                    updated: at,
+                })
+            }
+        };
+        open.chunks.insert(chunk.seq, text);
+        if last {
+            open.last_seq = Some(chunk.seq);
+        }
+        open.updated = at;
+        self.lines[open.line].text = open.chunks.values().map(String::as_str).collect();
+        if open.is_complete() {
+            self.open.remove(&key);
+        }
+    }
+
+    /// Whether another connection delivered the same text just before. Repeats
+    /// on the same connection are real, so they are kept.
+    fn is_duplicate(
+        &mut self,
+        link: usize,
+        sender: (u8, u8),
+        status: &STATUSTEXT_DATA,
+        chunk: TextChunk,
+        at: Instant,
+    ) -> bool {
+        while let Some(heard) = self.heard.front() {
+            if at.duration_since(heard.at).as_secs_f64() < DUPLICATE_SECS {
+                break;
+            }
+            self.heard.pop_front();
+        }
+        let duplicate = self.heard.iter().any(|heard| {
+            heard.link != link
+                && heard.sender == sender
+                && heard.chunk == chunk
+                && heard.severity == status.severity
+                && heard.text == status.text
+        });
+        if !duplicate {
+            self.heard.push_back(Heard {
+                at,
+                link,
+                sender,
+                chunk,
+                severity: status.severity,
+                text: status.text,
+            });
+        }
+        duplicate
+    }
+
+    /// Appends a line, dropping the oldest beyond `MAX_LINES`, and returns its index.
+    fn push_line(
+        &mut self,
+        time: DateTime<Local>,
+        sys_id: u8,
+        comp_id: u8,
+        severity: MavSeverity,
+        text: String,
+    ) -> usize {
+        self.lines.push_back(StatusLine {
+            time,
+            sys_id,
+            comp_id,
+            severity,
+            text,
+        });
+        if self.scroll > 0 && self.is_visible(&self.lines[self.lines.len() - 1]) {
+            // Keep the view still while scrolled back
+            self.scroll += 1;
+        }
+        if self.lines.len() > MAX_LINES {
+            self.lines.pop_front();
+            self.open.retain(|_, open| match open.line.checked_sub(1) {
+                Some(shifted) => {
+                    open.line = shifted;
+                    true
+                }
+                None => false,
+            });
+        }
+        self.lines.len() - 1
+    }
+
+    /// Takes over the filter, search and scroll position of `view`, an
+    /// earlier copy of this console, keeping the same lines in sight.
+    pub fn keep_view_of(&mut self, view: &Console) {
+        self.max_severity = view.max_severity;
+        self.search = view.search.clone();
+        self.search_active = view.search_active;
+        self.scroll = 0;
+        if view.scroll > 0 {
+            let visible = |console: &Console| {
+                console
+                    .lines
+                    .iter()
+                    .filter(|line| self.is_visible(line))
+                    .count()
+            };
+            self.scroll = view.scroll + visible(self).saturating_sub(visible(view));
+        }
+    }
+
+    pub fn is_visible(&self, line: &StatusLine) -> bool {
+        line.severity as u8 <= self.max_severity
+            && (self.search.is_empty()
+                || line
+                    .text
+                    .to_lowercase()
+                    .contains(&self.search.to_lowercase()))
+    }
+
+    pub fn visible(&self) -> Vec<&StatusLine> {
+        self.lines.iter().filter(|l| self.is_visible(l)).collect()
+    }
+}
+
+/// Short name of a MAV_SEVERITY value.
+pub fn severity_name(value: u8) -> &'static str {
+    match value {
+        0 => "EMERG",
+        1 => "ALERT",
+        2 => "CRIT",
+        3 => "ERROR",
+        4 => "WARN",
+        5 => "NOTICE",
+        6 => "INFO",
+        _ => "DEBUG",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use mavlink::MavHeader;
+
+    use super::*;
+
+    fn push(
+        console: &mut Console,
+        link: usize,
+        sys_id: u8,
+        status: &STATUSTEXT_DATA,
+        chunk: TextChunk,
+        at: Instant,
+    ) {
+        console.push(&ReceivedMessage {
+            link,
+            header: MavHeader {
+                system_id: sys_id,
+                component_id: 1,
+                sequence: 0,
+            },
+            message: MavMessage::STATUSTEXT(status.clone()),
+            received_at: at,
+            timestamp: Utc::now(),
+            frame_len: 63,
+            text_chunk: Some(chunk),
+        });
+    }
+
+    fn status(severity: MavSeverity, text: &str) -> STATUSTEXT_DATA {
+        let mut data = STATUSTEXT_DATA {
+            severity,
+            text: [0; 50],
+        };
+        data.text[..text.len()].copy_from_slice(text.as_bytes());
+        data
+    }
+
+    fn chunk(id: u16, seq: u8) -> TextChunk {
+        TextChunk { id, seq }
+    }
+
+    fn texts(console: &Console) -> Vec<&str> {
+        console.lines.iter().map(|l| l.text.as_str()).collect()
+    }
+
+    const FULL: &str = "PreArm: Compass not calibrated, check orientation ";
+    const INFO: MavSeverity = MavSeverity::MAV_SEVERITY_INFO;
+
+    #[test]
+    fn joins_interleaved_texts_by_id() {
+        let mut console = Console::default();
+        let t = Instant::now();
+        push(&mut console, 0, 1, &status(INFO, FULL), chunk(7, 0), t);
+        push(&mut console, 0, 1, &status(INFO, FULL), chunk(8, 0), t);
+        push(
+            &mut console,
+            0,
+            1,
+            &status(INFO, "standalone"),
+            chunk(0, 0),
+            t,
+        );
+        push(
+            &mut console,
+            0,
+            1,
+            &status(INFO, "and seven"),
+            chunk(7, 1),
+            t,
+        );
+        push(
+            &mut console,
+            0,
+            1,
+            &status(INFO, "and eight"),
+            chunk(8, 1),
+            t,
+        );
+        assert_eq!(
+            texts(&console),
+            [
+                format!("{}and seven", FULL),
+                format!("{}and eight", FULL),
+                "standalone".to_string(),
+            ]
+        );
+        assert!(console.open.is_empty());
+    }
+
+    #[test]
+    fn orders_chunks_by_sequence() {
+        let mut console = Console::default();
+        let t = Instant::now();
+        push(&mut console, 0, 1, &status(INFO, "end"), chunk(3, 2), t);
+        push(&mut console, 0, 1, &status(INFO, FULL), chunk(3, 0), t);
+        assert_eq!(console.open.len(), 1, "chunk 1 is still missing");
+        push(&mut console, 0, 1, &status(INFO, FULL), chunk(3, 1), t);
+        assert_eq!(texts(&console), [format!("{}{}end", FULL, FULL)]);
+        assert!(console.open.is_empty());
+    }
+
+    #[test]
+    fn keeps_full_texts_without_an_id_apart() {
+        let mut console = Console::default();
+        let t = Instant::now();
+        push(&mut console, 0, 1, &status(INFO, FULL), chunk(0, 0), t);
+        push(&mut console, 0, 1, &status(INFO, FULL), chunk(0, 0), t);
+        assert_eq!(console.lines.len(), 2);
+    }
+
+    #[test]
+    fn ids_are_per_sender_and_late_chunks_still_join() {
+        let mut console = Console::default();
+        let t = Instant::now();
+        push(&mut console, 0, 1, &status(INFO, FULL), chunk(5, 0), t);
+        push(
+            &mut console,
+            0,
+            2,
+            &status(INFO, "other vehicle"),
+            chunk(5, 0),
+            t,
+        );
+        push(
+            &mut console,
+            0,
+            1,
+            &status(INFO, "late"),
+            chunk(5, 1),
+            t + Duration::from_secs(2),
+        );
+        assert_eq!(
+            texts(&console),
+            [format!("{}late", FULL), "other vehicle".to_string()]
+        );
+        // Past the timeout the same id starts over
+        push(&mut console, 0, 1, &status(INFO, FULL), chunk(9, 0), t);
+        push(
+            &mut console,
+            0,
+            1,
+            &status(INFO, "too late"),
+            chunk(9, 1),
+            t + Duration::from_secs(10),
+        );
+        assert_eq!(console.lines.len(), 4);
+    }
+
+    #[test]
+    fn shows_a_text_from_several_connections_once() {
+        let mut console = Console::default();
+        let t = Instant::now();
+        let ms = Duration::from_millis;
+        push(&mut console, 0, 1, &status(INFO, "Arming"), chunk(0, 0), t);
+        push(
+            &mut console,
+            1,
+            1,
+            &status(INFO, "Arming"),
+            chunk(0, 0),
+            t + ms(30),
+        );
+        // The same text again on the same connection is a new message
+        push(
+            &mut console,
+            0,
+            1,
+            &status(INFO, "Arming"),
+            chunk(0, 0),
+            t + ms(40),
+        );
+        assert_eq!(texts(&console), ["Arming", "Arming"]);
+        // Once connection 0 goes quiet, connection 1 is heard on its own
+        push(
+            &mut console,
+            1,
+            1,
+            &status(INFO, "Disarmed"),
+            chunk(0, 0),
+            t + ms(3000),
+        );
+        assert_eq!(console.lines.len(), 3);
+    }
+
+    #[test]
+    fn drops_the_oldest_lines_beyond_the_cap() {
+        let mut console = Console::default();
+        let t = Instant::now();
+        push(&mut console, 0, 2, &status(INFO, FULL), chunk(1, 0), t);
+        for n in 0..MAX_LINES {
+            push(
+                &mut console,
+                0,
+                1,
+                &status(INFO, &n.to_string()),
+                chunk(0, 0),
+                t,
+            );
+        }
+        assert_eq!(console.lines.len(), MAX_LINES);
+        assert_eq!(console.lines[0].text, "0");
+        // The open text scrolled out, so its next chunk starts a new line
+        push(&mut console, 0, 2, &status(INFO, "end"), chunk(1, 1), t);
+        assert_eq!(console.lines[MAX_LINES - 1].text, "end");
+
+        push(&mut console, 0, 2, &status(INFO, FULL), chunk(2, 0), t);
+        push(&mut console, 0, 1, &status(INFO, "newest"), chunk(0, 0), t);
+        // The open text moved with the shift
+        push(&mut console, 0, 2, &status(INFO, "end"), chunk(2, 1), t);
+        assert_eq!(console.lines[MAX_LINES - 2].text, format!("{}end", FULL));
+    }
+}
@@ -0,0 +1,601 @@
+use std::fmt;
+use std::time::Instant;
+
+use serde::ser::{self, Serialize};
+
+use mavlink::ardupilotmega::MavMessage;
+
+use crate::schema;
+
+/// A decoded field value, captured through the message's `Serialize` impl so
+/// the Rust primitive types survive (unlike a round trip through JSON).
+#[derive(Clone)]
+pub enum FieldValue {
+    Number {
+        ty: &'static str,
+        value: f64,
+        text: String,
+    },
+    Text {
+        ty: &'static str,
+        text: String,
+    },
+    Enum {
+        ty: &'static str,
+        variant: String,
+    },
+    Flags {
+        ty: &'static str,
+        bits: u64,
+        /// Set flag names from the dialect joined by ` | `, then any unnamed bits
+        names: String,
+    },
+    List(Vec<FieldValue>),
+    Struct {
+        ty: &'static str,
+        fields: Vec<(&'static str, FieldValue)>,
+    },
+}
+
+impl FieldValue {
+    pub fn type_name(&self) -> String {
+        match self {
+            FieldValue::Number { ty, .. }
+            | FieldValue::Text { ty, .. }
+            | FieldValue::Enum { ty, .. }
+            | FieldValue::Flags { ty, .. }
+            | FieldValue::Struct { ty, .. } => ty.to_string(),
+            FieldValue::List(items) => match items.first() {
+                Some(first) => format!("[{}; {}]", first.type_name(), items.len()),
+                None => "[]".to_string(),
+            },
+        }
+    }
+
+    pub fn is_scalar(&self) -> bool {
+        !matches!(self, FieldValue::List(_) | FieldValue::Struct { .. })
+    }
+
+    /// Numeric value, if the field is a number or bitmask.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            FieldValue::Number { value, .. } => Some(*value),
+            FieldValue::Flags { bits, .. } => Some(*bits as f64),
+            _ => None,
+        }
+    }
+}
+
+/// Numbers compare by bit pattern, so a NaN field equals its previous NaN
+/// and is not flagged as changed on every update.
+impl PartialEq for FieldValue {
+    fn eq(&self, other: &Self) -> bool {
+        use FieldValue::*;
+        match (self, other) {
+            (
+                Number { ty, value, .. },
+                Number {
+                    ty: t, value: v, ..
+                },
+            ) => ty == t && value.to_bits() == v.to_bits(),
+            (Text { ty, text }, Text { ty: t, text: x }) => ty == t && text == x,
+            (Enum { ty, variant }, Enum { ty: t, variant: v }) => ty == t && variant == v,
+            (Flags { ty, bits, .. }, Flags { ty: t, bits: b, .. }) => ty == t && bits == b,
+            (List(items), List(other)) => items == other,
+            (Struct { ty, fields }, Struct { ty: t, fields: f }) => ty == t && fields == f,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldValue::Number { text, .. } | FieldValue::Text { text, .. } => f.write_str(text),
+            FieldValue::Enum { variant, .. } => f.write_str(variant),
+            FieldValue::Flags { bits, names, .. } if !names.is_empty() => {
+                write!(f, "{} ({:#x})", names, bits)
+            }
+            FieldValue::Flags { bits, .. } => write!(f, "{:#x}", bits),
+            FieldValue::List(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_str("]")
+            }
+            FieldValue::Struct { ty, .. } => f.write_str(ty),
+        }
+    }
+}
+
+/// Top-level fields of a message, without the serde `type` tag.
+pub fn message_fields(msg: &MavMessage) -> Vec<(&'static str, FieldValue)> {
+    match msg.serialize(FieldSerializer) {
+        Ok(FieldValue::Struct { fields, .. }) => fields
+            .into_iter()
+            .filter(|(name, _)| *name != "type")
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// One line of the rendered field tree.
+#[derive(Clone)]
+pub struct FieldRow {
+    /// Dotted path from the message root, e.g. `q[2]` or `origin.lat`
+    pub path: String,
+    pub depth: usize,
+    pub name: String,
+    pub ty: String,
+    pub value: FieldValue,
+    pub changed_at: Option<Instant>,
+}
+
+/// Flattens a message into tree rows. Lists of scalars stay on one row;
+/// structs and lists of structs get a row per child.
+pub fn field_rows(msg: &MavMessage) -> Vec<FieldRow> {
+    let mut rows = Vec::new();
+    for (name, value) in message_fields(msg) {
+        push_rows(&mut rows, name.to_string(), name.to_string(), 0, value);
+    }
+    rows
+}
+
+fn push_rows(
+    rows: &mut Vec<FieldRow>,
+    path: String,
+    name: String,
+    depth: usize,
+    value: FieldValue,
+) {
+    let children: Vec<(String, FieldValue)> = match &value {
+        FieldValue::Struct { fields, .. } => fields
+            .iter()
+            .map(|(n, v)| (n.to_string(), v.clone()))
+            .collect(),
+        FieldValue::List(items) if !items.iter().all(FieldValue::is_scalar) => items
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (format!("[{}]", i), v.clone()))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    rows.push(FieldRow {
+        path: path.clone(),
+        depth,
+        name,
+        ty: value.type_name(),
+        value,
+        changed_at: None,
+    });
+
+    for (child_name, child) in children {
+        let child_path = if child_name.starts_with('[') {
+            format!("{}{}", path, child_name)
+        } else {
+            format!("{}.{}", path, child_name)
+        };
+        push_rows(rows, child_path, child_name, depth + 1, child);
+    }
+}
+
+/// Carries change times over from the previous rows of the same message type,
+/// stamping `now` on rows whose value differs.
+pub fn mark_changes(rows: &mut [FieldRow], previous: &[FieldRow], now: Instant) {
+    for (i, row) in rows.iter_mut().enumerate() {
+        let prev = match previous.get(i) {
+            Some(p) if p.path == row.path => Some(p),
+            _ => previous.iter().find(|p| p.path == row.path),
+        };
+        row.changed_at = match prev {
+            Some(p) if p.value == row.value => p.changed_at,
+            Some(_) => Some(now),
+            None => None,
+        };
+    }
+}
+
+// --- Serializer ---
+
+#[derive(Debug)]
+pub struct FieldError(String);
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for FieldError {}
+
+impl ser::Error for FieldError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        FieldError(msg.to_string())
+    }
+}
+
+struct FieldSerializer;
+
+fn number(ty: &'static str, value: f64, text: String) -> Result<FieldValue, FieldError> {
+    Ok(FieldValue::Number { ty, value, text })
+}
+
+impl ser::Serializer for FieldSerializer {
+    type Ok = FieldValue;
+    type Error = FieldError;
+    type SerializeSeq = ListCollector;
+    type SerializeTuple = ListCollector;
+    type SerializeTupleStruct = ListCollector;
+    type SerializeTupleVariant = ListCollector;
+    type SerializeMap = ser::Impossible<FieldValue, FieldError>;
+    type SerializeStruct = StructCollector;
+    type SerializeStructVariant = StructCollector;
+
+    fn serialize_bool(self, v: bool) -> Result<FieldValue, FieldError> {
+        Ok(FieldValue::Text {
+            ty: "bool",
+            text: v.to_string(),
+        })
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<FieldValue, FieldError> {
+        number("i8", v as f64, v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<FieldValue, FieldError> {
+        number("i16", v as f64, v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<FieldValue, FieldError> {
+        number("i32", v as f64, v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<FieldValue, FieldError> {
+        number("i64", v as f64, v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<FieldValue, FieldError> {
+        number("u8", v as f64, v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<FieldValue, FieldError> {
+        number("u16", v as f64, v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<FieldValue, FieldError> {
+        number("u32", v as f64, v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<FieldValue, FieldError> {
+        number("u64", v as f64, v.to_string())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<FieldValue, FieldError> {
+        number("f32", v as f64, v.to_string())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<FieldValue, FieldError> {
+        number("f64", v, v.to_string())
+    }
+
+    fn serialize_char(self, v: char) -> Result<FieldValue, FieldError> {
+        Ok(FieldValue::Text {
+            ty: "char",
+            text: format!("{:?}", v),
+        })
+    }
+
+    fn serialize_str(self, v: &str) -> Result<FieldValue, FieldError> {
+        Ok(FieldValue::Text {
+            ty: "str",
+            text: v.to_string(),
+        })
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<FieldValue, FieldError> {
+        Ok(FieldValue::List(
+            v.iter()
+                .map(|b| FieldValue::Number {
+                    ty: "u8",
+                    value: *b as f64,
+                    text: b.to_string(),
+                })
+                .collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<FieldValue, FieldError> {
+        Ok(FieldValue::Text {
+            ty: "option",
+            text: "None".to_string(),
+        })
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<FieldValue, FieldError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<FieldValue, FieldError> {
+        Ok(FieldValue::Text {
+            ty: "()",
+            text: "()".to_string(),
+        })
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<FieldValue, FieldError> {
+        Ok(FieldValue::Text {
+            ty: name,
+            text: name.to_string(),
+        })
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<FieldValue, FieldError> {
+        Ok(FieldValue::Enum {
+            ty: name,
+            variant: variant.to_string(),
+        })
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<FieldValue, FieldError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<FieldValue, FieldError> {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ListCollector, FieldError> {
+        Ok(ListCollector(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ListCollector, FieldError> {
+        Ok(ListCollector(Vec::with_capacity(len)))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<ListCollector, FieldError> {
+        Ok(ListCollector(Vec::with_capacity(len)))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        len: usize,
+    ) -> Result<ListCollector, FieldError> {
+        Ok(ListCollector(Vec::with_capacity(len)))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, FieldError> {
+        Err(FieldError(
+            "maps are not used by MAVLink messages".to_string(),
+        ))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<StructCollector, FieldError> {
+        Ok(StructCollector {
+            ty: name,
+            fields: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<StructCollector, FieldError> {
+        Ok(StructCollector {
+            ty: variant,
+            fields: Vec::with_capacity(len),
+        })
+    }
+}
+
+struct ListCollector(Vec<FieldValue>);
+
+impl ListCollector {
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), FieldError> {
+        self.0.push(value.serialize(FieldSerializer)?);
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for ListCollector {
+    type Ok = FieldValue;
+    type Error = FieldError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), FieldError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<FieldValue, FieldError> {
+        Ok(FieldValue::List(self.0))
+    }
+}
+
+impl ser::SerializeTuple for ListCollector {
+    type Ok = FieldValue;
+    type Error = FieldError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), FieldError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<FieldValue, FieldError> {
+        Ok(FieldValue::List(self.0))
+    }
+}
+
+impl ser::SerializeTupleStruct for ListCollector {
+    type Ok = FieldValue;
+    type Error = FieldError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), FieldError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<FieldValue, FieldError> {
+        Ok(FieldValue::List(self.0))
+    }
+}
+
+impl ser::SerializeTupleVariant for ListCollector {
+    type Ok = FieldValue;
+    type Error = FieldError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), FieldError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<FieldValue, FieldError> {
+        Ok(FieldValue::List(self.0))
+    }
+}
+
+/// Names of the flags set in `bits`, from the dialect's bitmask enum: serde
+/// only exposes the raw bits.
+fn flag_names(ty: &str, bits: u64) -> String {
+    let Some(def) = schema::find_enum_type(ty) else {
+        return String::new();
+    };
+    let mut names = Vec::new();
+    let mut rest = bits;
+    for entry in &def.entries {
+        if entry.value != 0 && bits & entry.value == entry.value {
+            names.push(entry.name.clone());
+            rest &= !entry.value;
+        }
+    }
+    if rest != 0 && !names.is_empty() {
+        names.push(format!("{:#x}", rest));
+    }
+    names.join(" | ")
+}
+
+struct StructCollector {
+    ty: &'static str,
+    fields: Vec<(&'static str, FieldValue)>,
+}
+
+impl StructCollector {
+    fn finish(self) -> FieldValue {
+        match self.fields.as_slice() {
+            // Internally tagged unit variants: the MAVLink enums
+            [("type", FieldValue::Text { text, .. })] => FieldValue::Enum {
+                ty: self.ty,
+                variant: text.clone(),
+            },
+            // bitflags structs
+            [("bits", FieldValue::Number { value, .. })] => FieldValue::Flags {
+                ty: self.ty,
+                bits: *value as u64,
+                names: flag_names(self.ty, *value as u64),
+            },
+            _ => FieldValue::Struct {
+                ty: self.ty,
+                fields: self.fields,
+            },
+        }
+    }
+}
+
+impl ser::SerializeStruct for StructCollector {
+    type Ok = FieldValue;
+    type Error = FieldError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), FieldError> {
+        self.fields.push((key, value.serialize(FieldSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<FieldValue, FieldError> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeStructVariant for StructCollector {
+    type Ok = FieldValue;
+    type Error = FieldError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), FieldError> {
+        self.fields.push((key, value.serialize(FieldSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<FieldValue, FieldError> {
+        Ok(self.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::ardupilotmega::*;
+
+    use super::*;
+
+    #[test]
+    fn flag_names_come_from_the_dialect() {
+        let msg = MavMessage::HEARTBEAT(HEARTBEAT_DATA {
+            base_mode: MavModeFlag::MAV_MODE_FLAG_SAFETY_ARMED
+                | MavModeFlag::MAV_MODE_FLAG_CUSTOM_MODE_ENABLED,
+            ..Default::default()
+        });
+        let fields = message_fields(&msg);
+        let (_, base_mode) = fields.iter().find(|(n, _)| *n == "base_mode").unwrap();
+        assert_eq!(
+            base_mode.to_string(),
+            "MAV_MODE_FLAG_SAFETY_ARMED | MAV_MODE_FLAG_CUSTOM_MODE_ENABLED (0x81)"
+        );
+    }
+
+    #[test]
+    fn nan_equals_nan() {
+        let msg = MavMessage::ATTITUDE(ATTITUDE_DATA {
+            roll: f32::NAN,
+            ..Default::default()
+        });
+        let now = Instant::now();
+        let previous = field_rows(&msg);
+        let mut rows = field_rows(&msg);
+        mark_changes(&mut rows, &previous, now);
+        assert!(rows.iter().all(|row| row.changed_at.is_none()));
+    }
+}
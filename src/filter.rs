@@ -0,0 +1,766 @@
+use std::fmt;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::fields::{FieldRow, FieldValue};
+
+/// A field value as seen by filter predicates.
+pub enum Value {
+    Number(f64),
+    Text(String),
+}
+
+impl From<&FieldValue> for Value {
+    fn from(value: &FieldValue) -> Self {
+        match value {
+            FieldValue::Enum { variant, .. } => Value::Text(variant.clone()),
+            FieldValue::Text { text, .. } => Value::Text(text.clone()),
+            other => match other.as_f64() {
+                Some(n) => Value::Number(n),
+                None => Value::Text(other.to_string()),
+            },
+        }
+    }
+}
+
+/// Something a filter expression can be evaluated against: a live table
+/// entry, a recorded message or a message about to be recorded.
+pub trait Subject {
+    fn name(&self) -> &str;
+    fn system_id(&self) -> u8;
+    fn component_id(&self) -> u8;
+    fn message_id(&self) -> u32;
+
+    /// Receive rate; only known for live table entries.
+    fn hz(&self) -> Option<f64> {
+        None
+    }
+
+    /// Messages received so far; only known for live table entries.
+    fn count(&self) -> Option<u64> {
+        None
+    }
+
+    /// Seconds since the latest message; only known for live table entries.
+    fn age(&self) -> Option<f64> {
+        None
+    }
+
+    /// Name of the connection the message came in on, or of every
+    /// connection joined by `+` for entries merged across them.
+    fn link(&self) -> Option<String> {
+        None
+    }
+
+    /// Value of a field of the latest message, by path (e.g. `q[2]`).
+    fn field(&self, path: &str) -> Option<Value>;
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub message: String,
+    /// Character (not byte) offset into the expression
+    pub position: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A parsed filter expression.
+///
+/// ```text
+/// expr      := term (("or") term)*
+/// term      := factor (("and") factor)*
+/// factor    := "not" factor | "(" expr ")" | predicate
+/// predicate := operand op literal | operand "in" "(" literal, ... ")" | word | /regex/
+/// operand   := name | id | sys | comp | hz | count | age | link | MESSAGE.field
+/// op        := == | != | < | <= | > | >= | ~
+/// ```
+///
+/// A bare word keeps the old behaviour: a case-insensitive substring of the
+/// message name or `sys:comp`, or a name glob if it contains `*` or `?`.
+/// Several plain words with nothing joining them, which the grammar rejects,
+/// are taken as one substring the way the old filter did.
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Contains(String),
+    NameMatches(Regex),
+    Compare {
+        operand: Operand,
+        op: Op,
+        literal: Literal,
+    },
+    In {
+        operand: Operand,
+        literals: Vec<Literal>,
+    },
+}
+
+pub enum Operand {
+    Name,
+    Id,
+    Sys,
+    Comp,
+    Hz,
+    Count,
+    Age,
+    Link,
+    Field { message: String, path: String },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Match,
+}
+
+pub enum Literal {
+    Number(f64),
+    /// A word or quoted string, compiled as a case-insensitive glob
+    Text(Regex),
+    Regex(Regex),
+}
+
+impl Expr {
+    /// Parses an expression; an empty or blank string yields `None`.
+    pub fn parse(input: &str) -> Result<Option<Expr>, ParseError> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Ok(None);
+        }
+        let plain_words = tokens.iter().all(|(token, _)| {
+            matches!(token, Token::Word(w)
+                if !["and", "or", "not", "in"].iter().any(|k| w.eq_ignore_ascii_case(k)))
+        });
+        if tokens.len() > 1 && plain_words {
+            return Ok(Some(Expr::Contains(input.trim().to_string())));
+        }
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end: input.chars().count(),
+        };
+        let expr = parser.or()?;
+        match parser.peek() {
+            None => Ok(Some(expr)),
+            Some((_, at)) => Err(error("unexpected input", at)),
+        }
+    }
+
+    /// Whether any predicate compares the given operand.
+    pub fn uses(&self, pred: &impl Fn(&Operand) -> bool) -> bool {
+        match self {
+            Expr::And(a, b) | Expr::Or(a, b) => a.uses(pred) || b.uses(pred),
+            Expr::Not(e) => e.uses(pred),
+            Expr::Contains(_) | Expr::NameMatches(_) => false,
+            Expr::Compare { operand, .. } | Expr::In { operand, .. } => pred(operand),
+        }
+    }
+
+    pub fn matches<S: Subject + ?Sized>(&self, subject: &S) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(subject) && b.matches(subject),
+            Expr::Or(a, b) => a.matches(subject) || b.matches(subject),
+            Expr::Not(e) => !e.matches(subject),
+            Expr::Contains(text) => {
+                let text = text.to_uppercase();
+                subject.name().to_uppercase().contains(&text)
+                    || format!("{}:{}", subject.system_id(), subject.component_id()).contains(&text)
+            }
+            Expr::NameMatches(re) => re.is_match(subject.name()),
+            Expr::Compare {
+                operand,
+                op,
+                literal,
+            } => operand
+                .value(subject)
+                .is_some_and(|value| compare(&value, *op, literal)),
+            Expr::In { operand, literals } => operand.value(subject).is_some_and(|value| {
+                literals
+                    .iter()
+                    .any(|literal| compare(&value, Op::Eq, literal))
+            }),
+        }
+    }
+}
+
+impl Operand {
+    fn value<S: Subject + ?Sized>(&self, subject: &S) -> Option<Value> {
+        match self {
+            Operand::Name => Some(Value::Text(subject.name().to_string())),
+            Operand::Id => Some(Value::Number(subject.message_id() as f64)),
+            Operand::Sys => Some(Value::Number(subject.system_id() as f64)),
+            Operand::Comp => Some(Value::Number(subject.component_id() as f64)),
+            Operand::Hz => subject.hz().map(Value::Number),
+            Operand::Count => subject.count().map(|c| Value::Number(c as f64)),
+            Operand::Age => subject.age().map(Value::Number),
+            Operand::Link => subject.link().map(Value::Text),
+            Operand::Field { message, path } => {
+                if !subject.name().eq_ignore_ascii_case(message) {
+                    return None;
+                }
+                subject.field(path)
+            }
+        }
+    }
+
+    fn is_numeric(&self) -> bool {
+        !matches!(self, Operand::Name | Operand::Link | Operand::Field { .. })
+    }
+}
+
+fn compare(value: &Value, op: Op, literal: &Literal) -> bool {
+    match (value, literal) {
+        (Value::Number(v), Literal::Number(n)) => match op {
+            Op::Eq => v == n,
+            Op::Ne => v != n,
+            Op::Lt => v < n,
+            Op::Le => v <= n,
+            Op::Gt => v > n,
+            Op::Ge => v >= n,
+            Op::Match => false,
+        },
+        (Value::Text(text), Literal::Text(re) | Literal::Regex(re)) => match op {
+            Op::Eq | Op::Match => re.is_match(text),
+            Op::Ne => !re.is_match(text),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+// --- Tokenizer ---
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    Comma,
+    Op(Op),
+    Word(String),
+    Str(String),
+    Regex(String),
+}
+
+fn error(message: &str, position: usize) -> ParseError {
+    ParseError {
+        message: message.to_string(),
+        position,
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || "_.*?:-+[]".contains(c)
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let mut tokens = Vec::new();
+    // Positions count characters, so error columns line up with what is typed
+    let mut chars = input.chars().enumerate().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let token = match c {
+            '(' | ')' | ',' | '~' => {
+                chars.next();
+                match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    ',' => Token::Comma,
+                    _ => Token::Op(Op::Match),
+                }
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let eq = chars.next_if(|&(_, c)| c == '=').is_some();
+                Token::Op(match (c, eq) {
+                    ('=', _) => Op::Eq,
+                    ('!', true) => Op::Ne,
+                    ('<', false) => Op::Lt,
+                    ('<', true) => Op::Le,
+                    ('>', false) => Op::Gt,
+                    ('>', true) => Op::Ge,
+                    _ => return Err(error("expected !=", start)),
+                })
+            }
+            '"' | '\'' | '/' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '\\')) if c == '/' => {
+                            // Keep regex escapes, but allow \/ for a literal slash
+                            match chars.next() {
+                                Some((_, '/')) => text.push('/'),
+                                Some((_, e)) => {
+                                    text.push('\\');
+                                    text.push(e);
+                                }
+                                None => break,
+                            }
+                        }
+                        Some((_, end)) if end == c => break,
+                        Some((_, ch)) => text.push(ch),
+                        None => return Err(error("unterminated string", start)),
+                    }
+                }
+                if c == '/' {
+                    Token::Regex(text)
+                } else {
+                    Token::Str(text)
+                }
+            }
+            c if is_word_char(c) => {
+                let mut word = String::new();
+                while let Some((_, ch)) = chars.next_if(|&(_, ch)| is_word_char(ch)) {
+                    word.push(ch);
+                }
+                Token::Word(word)
+            }
+            _ => return Err(error(&format!("unexpected '{}'", c), start)),
+        };
+        tokens.push((token, start));
+    }
+    Ok(tokens)
+}
+
+// --- Parser ---
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// Position reported for errors at the end of input
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<(&Token, usize)> {
+        self.tokens.get(self.pos).map(|(t, at)| (t, *at))
+    }
+
+    fn next(&mut self) -> Option<(Token, usize)> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some((Token::Word(w), _)) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<(), ParseError> {
+        match self.next() {
+            Some((token, _)) if token == expected => Ok(()),
+            Some((_, at)) => Err(error(&format!("expected {}", what), at)),
+            None => Err(error(&format!("expected {}", what), self.end)),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.and()?;
+        while self.at_keyword("or") {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.factor()?;
+        while self.at_keyword("and") {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.factor()?));
+        }
+        Ok(expr)
+    }
+
+    fn factor(&mut self) -> Result<Expr, ParseError> {
+        if self.at_keyword("not") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.factor()?)));
+        }
+        match self.next() {
+            Some((Token::LParen, _)) => {
+                let expr = self.or()?;
+                self.expect(Token::RParen, "')'")?;
+                Ok(expr)
+            }
+            Some((Token::Regex(pattern), at)) => Ok(Expr::NameMatches(regex(&pattern, at)?)),
+            Some((Token::Word(word), at)) => self.predicate(word, at),
+            Some((Token::Str(text), _)) => Ok(Expr::Contains(text)),
+            Some((_, at)) => Err(error("expected a predicate", at)),
+            None => Err(error("expected a predicate", self.end)),
+        }
+    }
+
+    fn predicate(&mut self, word: String, at: usize) -> Result<Expr, ParseError> {
+        let is_in = self.at_keyword("in");
+        let op = match self.peek() {
+            Some((Token::Op(op), _)) => Some(*op),
+            _ => None,
+        };
+        if op.is_none() && !is_in {
+            return Ok(if word.contains(['*', '?']) {
+                Expr::NameMatches(glob(&word, at)?)
+            } else {
+                Expr::Contains(word)
+            });
+        }
+
+        let operand = operand(&word, at)?;
+        self.pos += 1;
+
+        if is_in {
+            self.expect(Token::LParen, "'(' after in")?;
+            let mut literals = vec![self.literal(&operand)?];
+            while matches!(self.peek(), Some((Token::Comma, _))) {
+                self.pos += 1;
+                literals.push(self.literal(&operand)?);
+            }
+            self.expect(Token::RParen, "')'")?;
+            return Ok(Expr::In { operand, literals });
+        }
+
+        let op = op.unwrap_or(Op::Eq);
+        let op_at = self.tokens[self.pos - 1].1;
+        match (&operand, op) {
+            (Operand::Name | Operand::Link, Op::Lt | Op::Le | Op::Gt | Op::Ge) => {
+                return Err(error("name and link support ==, !=, ~ and in", op_at))
+            }
+            (o, Op::Match) if o.is_numeric() => {
+                return Err(error("~ needs a name or text field", op_at))
+            }
+            _ => {}
+        }
+        let literal = if op == Op::Match {
+            match self.next() {
+                Some((Token::Word(p) | Token::Str(p) | Token::Regex(p), at)) => {
+                    Literal::Regex(regex(&p, at)?)
+                }
+                Some((_, at)) => return Err(error("expected a regex", at)),
+                None => return Err(error("expected a regex", self.end)),
+            }
+        } else {
+            self.literal(&operand)?
+        };
+        Ok(Expr::Compare {
+            operand,
+            op,
+            literal,
+        })
+    }
+
+    fn literal(&mut self, operand: &Operand) -> Result<Literal, ParseError> {
+        let literal = match self.next() {
+            Some((Token::Word(word), at)) => match word.parse::<f64>() {
+                Ok(n) if !matches!(operand, Operand::Name | Operand::Link) => Literal::Number(n),
+                _ => Literal::Text(glob(&word, at)?),
+            },
+            Some((Token::Str(text), at)) => Literal::Text(glob(&text, at)?),
+            Some((Token::Regex(pattern), at)) => Literal::Regex(regex(&pattern, at)?),
+            Some((_, at)) => return Err(error("expected a value", at)),
+            None => return Err(error("expected a value", self.end)),
+        };
+        if operand.is_numeric() && !matches!(literal, Literal::Number(_)) {
+            return Err(error("expected a number", self.tokens[self.pos - 1].1));
+        }
+        Ok(literal)
+    }
+}
+
+fn operand(word: &str, at: usize) -> Result<Operand, ParseError> {
+    Ok(match word.to_lowercase().as_str() {
+        "name" => Operand::Name,
+        "id" => Operand::Id,
+        "sys" => Operand::Sys,
+        "comp" => Operand::Comp,
+        "hz" => Operand::Hz,
+        "count" => Operand::Count,
+        "age" => Operand::Age,
+        "link" => Operand::Link,
+        _ => match word.split_once('.') {
+            Some((message, path)) if !message.is_empty() && !path.is_empty() => Operand::Field {
+                message: message.to_string(),
+                path: path.to_string(),
+            },
+            _ => {
+                return Err(error(
+                    &format!("unknown field '{}' (use MESSAGE.field)", word),
+                    at,
+                ))
+            }
+        },
+    })
+}
+
+fn regex(pattern: &str, at: usize) -> Result<Regex, ParseError> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|_| error("invalid regex", at))
+}
+
+/// Compiles a `*`/`?` glob into an anchored, case-insensitive regex.
+fn glob(pattern: &str, at: usize) -> Result<Regex, ParseError> {
+    let mut re = String::from("^");
+    for c in pattern.chars() {
+        match c {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    regex(&re, at)
+}
+
+/// Finds a field of a decoded message by path, including an element of a list
+/// of scalars (`q[2]`), which stays on one row.
+pub fn row_field(rows: &[FieldRow], path: &str) -> Option<Value> {
+    if let Some(row) = rows.iter().find(|row| row.path.eq_ignore_ascii_case(path)) {
+        return Some(Value::from(&row.value));
+    }
+    let (list, index) = split_index(path)?;
+    match &rows
+        .iter()
+        .find(|row| row.path.eq_ignore_ascii_case(list))?
+        .value
+    {
+        FieldValue::List(items) => items.get(index).map(Value::from),
+        _ => None,
+    }
+}
+
+/// `q[2]` -> (`q`, 2)
+fn split_index(path: &str) -> Option<(&str, usize)> {
+    let (list, index) = path.strip_suffix(']')?.rsplit_once('[')?;
+    Some((list, index.parse().ok()?))
+}
+
+/// Finds a field in a recorded message, which stores the pretty-printed Debug
+/// text rather than typed values. Paths are built like `FieldRow::path`:
+/// `name`, `list[i]` and `outer.inner`.
+pub fn debug_field(text: &str, path: &str) -> Option<Value> {
+    debug_leaves(text)
+        .into_iter()
+        .find(|(leaf, _)| leaf.eq_ignore_ascii_case(path))
+        .map(|(_, value)| match value.parse::<f64>() {
+            Ok(n) => Value::Number(n),
+            Err(_) => Value::Text(value.trim_matches('"').to_string()),
+        })
+}
+
+/// Every scalar in `{:#?}` output with its path. The message variant and its
+/// `*_DATA` struct are the unnamed outer levels and add nothing to the path.
+fn debug_leaves(text: &str) -> Vec<(String, &str)> {
+    struct Level {
+        path: String,
+        /// Next element index, for lists
+        index: Option<usize>,
+    }
+
+    let mut levels: Vec<Level> = Vec::new();
+    let mut leaves = Vec::new();
+    for line in text.lines() {
+        let line = line.trim().trim_end_matches(',');
+        if matches!(line, "}" | "]" | ")") {
+            levels.pop();
+            continue;
+        }
+        let (name, value) = match line.split_once(": ") {
+            Some((name, value)) => (Some(name), value),
+            None => (None, line),
+        };
+        let path = match levels.last_mut() {
+            Some(Level {
+                path,
+                index: Some(i),
+            }) => {
+                *i += 1;
+                format!("{}[{}]", path, *i - 1)
+            }
+            Some(Level { path, .. }) => match name {
+                Some(name) if path.is_empty() => name.to_string(),
+                Some(name) => format!("{}.{}", path, name),
+                None => path.clone(),
+            },
+            None => String::new(),
+        };
+        if value.ends_with(['{', '[', '(']) {
+            levels.push(Level {
+                path,
+                index: value.ends_with('[').then_some(0),
+            });
+        } else if !path.is_empty() {
+            leaves.push((path, value));
+        }
+    }
+    leaves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A message with fixed properties and a few fields.
+    struct Msg {
+        name: &'static str,
+        sys: u8,
+        fields: Vec<(&'static str, Value)>,
+    }
+
+    impl Subject for Msg {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn system_id(&self) -> u8 {
+            self.sys
+        }
+
+        fn component_id(&self) -> u8 {
+            1
+        }
+
+        fn message_id(&self) -> u32 {
+            24
+        }
+
+        fn hz(&self) -> Option<f64> {
+            Some(10.0)
+        }
+
+        fn field(&self, path: &str) -> Option<Value> {
+            self.fields
+                .iter()
+                .find(|(name, _)| *name == path)
+                .map(|(_, value)| match value {
+                    Value::Number(n) => Value::Number(*n),
+                    Value::Text(t) => Value::Text(t.clone()),
+                })
+        }
+    }
+
+    fn gps() -> Msg {
+        Msg {
+            name: "GPS_RAW_INT",
+            sys: 1,
+            fields: vec![
+                ("fix_type", Value::Text("GPS_FIX_TYPE_3D_FIX".to_string())),
+                ("satellites_visible", Value::Number(9.0)),
+            ],
+        }
+    }
+
+    fn matches(filter: &str) -> bool {
+        Expr::parse(filter).unwrap().unwrap().matches(&gps())
+    }
+
+    fn error_at(filter: &str) -> usize {
+        Expr::parse(filter).err().unwrap().position
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert!(matches("sys == 2 and hz > 100 or gps"));
+        assert!(!matches("sys == 2 and (hz > 100 or gps)"));
+        assert!(matches("not sys == 2 and gps"));
+        assert!(!matches("not (sys == 1 and gps)"));
+    }
+
+    #[test]
+    fn compares_numbers() {
+        assert!(matches("id == 24"));
+        assert!(matches("hz >= 10 and hz <= 10 and hz != 11"));
+        assert!(!matches("hz < 10 or hz > 10"));
+        assert!(matches("sys in (3, 1)"));
+        assert!(matches("GPS_RAW_INT.satellites_visible > 6"));
+    }
+
+    #[test]
+    fn coerces_between_text_and_numbers() {
+        // A number against a name or link is a glob, not a number
+        assert!(!matches("name == 24"));
+        // Text fields compare as globs, numbers against text never match
+        assert!(matches("gps_raw_int.fix_type == *3d*"));
+        assert!(!matches("GPS_RAW_INT.fix_type > 2"));
+        assert!(!matches("GPS_RAW_INT.satellites_visible == nine"));
+        assert!(matches("name ~ '^gps'"));
+        // Fields of other messages never match
+        assert!(!matches("ATTITUDE.roll > 0 or ATTITUDE.roll <= 0"));
+    }
+
+    #[test]
+    fn rejects_mistyped_literals() {
+        assert_eq!(error_at("hz > fast"), 5);
+        assert_eq!(error_at("name < 3"), 5);
+        assert_eq!(error_at("hz ~ 10"), 3);
+    }
+
+    #[test]
+    fn reports_error_positions_in_characters() {
+        assert_eq!(error_at("sys == 1 and"), 12);
+        assert_eq!(error_at("(gps"), 4);
+        assert_eq!(error_at("\"é\" and sys == 1 )"), 17);
+        assert_eq!(
+            Expr::parse("ü ! 1").err().unwrap().to_string(),
+            "expected != at column 3"
+        );
+    }
+
+    #[test]
+    fn several_plain_words_are_one_substring() {
+        let expr = Expr::parse("GPS RAW").unwrap().unwrap();
+        assert!(matches!(&expr, Expr::Contains(text) if text == "GPS RAW"));
+        assert!(Expr::parse("gps and").is_err());
+    }
+
+    const RECORDED: &str = "ATTITUDE_QUATERNION(
+    ATTITUDE_QUATERNION_DATA {
+        time_boot_ms: 100,
+        q: [
+            1.0,
+            0.5,
+        ],
+        origin: Position {
+            lat: 47,
+            name: \"home\",
+        },
+        mode: MAV_MODE_GUIDED_ARMED,
+    },
+)";
+
+    #[test]
+    fn finds_nested_fields_in_recorded_messages() {
+        let number = |path| match debug_field(RECORDED, path) {
+            Some(Value::Number(n)) => Some(n),
+            _ => None,
+        };
+        assert_eq!(number("time_boot_ms"), Some(100.0));
+        assert_eq!(number("Q[1]"), Some(0.5));
+        assert_eq!(number("origin.lat"), Some(47.0));
+        assert!(
+            matches!(debug_field(RECORDED, "origin.name"), Some(Value::Text(t)) if t == "home")
+        );
+        assert!(
+            matches!(debug_field(RECORDED, "mode"), Some(Value::Text(t)) if t == "MAV_MODE_GUIDED_ARMED")
+        );
+        assert!(debug_field(RECORDED, "lat").is_none());
+        assert!(debug_field(RECORDED, "q[2]").is_none());
+    }
+}
@@ -0,0 +1,456 @@
+use std::io::{self, Read};
+
+use mavlink::ardupilotmega::{MavMessage, STATUSTEXT_DATA};
+use mavlink::{MavHeader, MavlinkVersion, Message, MessageData};
+
+const STX_V1: u8 = 0xFE;
+const STX_V2: u8 = 0xFD;
+const HEADER_V1: usize = 6;
+const HEADER_V2: usize = 10;
+const CHECKSUM_LEN: usize = 2;
+const SIGNATURE_LEN: usize = 13;
+/// The only MAVLink 2 incompatibility flag: the frame carries a signature
+const IFLAG_SIGNED: u8 = 0x01;
+/// Bytes asked of the reader at a time
+const READ_CHUNK: usize = 8192;
+
+/// The `id` and `chunk_seq` extension fields of a STATUSTEXT, which
+/// rust-mavlink 0.13 does not decode. Id 0 marks a text complete in one
+/// message; senders that predate the fields always send 0.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct TextChunk {
+    pub id: u16,
+    pub seq: u8,
+}
+
+impl TextChunk {
+    /// Reads the bytes after `severity` and `text`. MAVLink 2 trims trailing
+    /// zero bytes from the payload, so missing ones read as 0.
+    fn from_payload(payload: &[u8]) -> Self {
+        let byte = |i: usize| payload.get(i).copied().unwrap_or(0);
+        let at = STATUSTEXT_DATA::ENCODED_LEN;
+        Self {
+            id: u16::from_le_bytes([byte(at), byte(at + 1)]),
+            seq: byte(at + 2),
+        }
+    }
+}
+
+/// What the framer found next in the byte stream.
+#[allow(clippy::large_enum_variant)]
+pub enum FrameEvent {
+    Message {
+        header: MavHeader,
+        message: MavMessage,
+        /// Bytes the frame took on the wire, signature included
+        len: usize,
+        /// Set for STATUSTEXT
+        text_chunk: Option<TextChunk>,
+    },
+    /// A run of bytes that were not part of any valid frame
+    Garbage(usize),
+    /// A frame of a known message whose checksum did not match
+    BadCrc { message_id: u32 },
+    /// A frame that passed the CRC check but could not be decoded
+    Invalid(String),
+}
+
+enum Candidate {
+    /// More bytes are needed to tell
+    Incomplete,
+    /// The start marker is not followed by anything that could be a frame
+    NotAFrame,
+    BadCrc(u32),
+    Frame {
+        version: MavlinkVersion,
+        header_len: usize,
+        payload_len: usize,
+        len: usize,
+    },
+}
+
+/// Splits a raw byte stream into MAVLink 1 and 2 frames, reporting what
+/// rust-mavlink's readers silently skip: bytes between frames and frames with
+/// a bad checksum. Like rust-mavlink, it resynchronizes one byte after a start
+/// marker that did not lead to a valid frame, since the marker value can occur
+/// inside a frame; those bytes are counted as garbage.
+pub struct Framer<R> {
+    reader: R,
+    buf: Vec<u8>,
+    /// Start of the unread part of `buf`
+    pos: usize,
+    /// Bytes skipped since the last `Garbage` event
+    garbage: usize,
+    /// A bad checksum was reported and no valid frame has followed yet. Markers
+    /// inside the rest of a corrupt frame or in noise often pass for the start
+    /// of another frame, so only the first bad checksum of a resync counts.
+    resyncing: bool,
+}
+
+impl<R: Read> Framer<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            pos: 0,
+            garbage: 0,
+            resyncing: false,
+        }
+    }
+
+    /// Reads until the next event. A garbage run is reported before whatever
+    /// follows it and before blocking for more input. A reader returning no
+    /// bytes ends the stream with `UnexpectedEof`.
+    pub fn next_event(&mut self) -> io::Result<FrameEvent> {
+        loop {
+            let unread = &self.buf[self.pos..];
+            match unread.iter().position(|&b| b == STX_V1 || b == STX_V2) {
+                Some(skip) => {
+                    self.garbage += skip;
+                    self.pos += skip;
+                }
+                None => {
+                    self.garbage += unread.len();
+                    self.pos = self.buf.len();
+                    if let Some(event) = self.take_garbage() {
+                        return Ok(event);
+                    }
+                    self.fill()?;
+                    continue;
+                }
+            }
+
+            match self.candidate() {
+                Candidate::Incomplete => {
+                    if let Some(event) = self.take_garbage() {
+                        return Ok(event);
+                    }
+                    match self.fill() {
+                        // A partial frame at the end of the stream is garbage too
+                        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                            self.garbage += self.buf.len() - self.pos;
+                            self.pos = self.buf.len();
+                            return self.take_garbage().ok_or(e);
+                        }
+                        result => result?,
+                    }
+                }
+                Candidate::NotAFrame => {
+                    self.pos += 1;
+                    self.garbage += 1;
+                }
+                Candidate::BadCrc(message_id) => {
+                    self.pos += 1;
+                    self.garbage += 1;
+                    if !std::mem::replace(&mut self.resyncing, true) {
+                        return Ok(FrameEvent::BadCrc { message_id });
+                    }
+                }
+                Candidate::Frame {
+                    version,
+                    header_len,
+                    payload_len,
+                    len,
+                } => {
+                    if let Some(event) = self.take_garbage() {
+                        return Ok(event);
+                    }
+                    self.resyncing = false;
+                    let frame = &self.buf[self.pos..self.pos + len];
+                    let (sequence, system_id, component_id, message_id) = match version {
+                        MavlinkVersion::V1 => (frame[2], frame[3], frame[4], u32::from(frame[5])),
+                        MavlinkVersion::V2 => (
+                            frame[4],
+                            frame[5],
+                            frame[6],
+                            u32::from_le_bytes([frame[7], frame[8], frame[9], 0]),
+                        ),
+                    };
+                    let payload = &frame[header_len..header_len + payload_len];
+                    let event = match MavMessage::parse(version, message_id, payload) {
+                        Ok(message) => FrameEvent::Message {
+                            header: MavHeader {
+                                system_id,
+                                component_id,
+                                sequence,
+                            },
+                            message,
+                            len,
+                            text_chunk: (message_id == STATUSTEXT_DATA::ID)
+                                .then(|| TextChunk::from_payload(payload)),
+                        },
+                        Err(e) => FrameEvent::Invalid(e.to_string()),
+                    };
+                    self.pos += len;
+                    return Ok(event);
+                }
+            }
+        }
+    }
+
+    /// Classifies the bytes at `pos`, which start with a marker.
+    fn candidate(&self) -> Candidate {
+        let bytes = &self.buf[self.pos..];
+        let (version, header_len) = match bytes[0] {
+            STX_V1 => (MavlinkVersion::V1, HEADER_V1),
+            _ => (MavlinkVersion::V2, HEADER_V2),
+        };
+        if bytes.len() < header_len {
+            return Candidate::Incomplete;
+        }
+        let payload_len = usize::from(bytes[1]);
+        let (message_id, signature_len) = match version {
+            MavlinkVersion::V1 => (u32::from(bytes[5]), 0),
+            MavlinkVersion::V2 => {
+                let incompat_flags = bytes[2];
+                if incompat_flags & !IFLAG_SIGNED != 0 {
+                    return Candidate::NotAFrame;
+                }
+                let signature_len = if incompat_flags & IFLAG_SIGNED != 0 {
+                    SIGNATURE_LEN
+                } else {
+                    0
+                };
+                (
+                    u32::from_le_bytes([bytes[7], bytes[8], bytes[9], 0]),
+                    signature_len,
+                )
+            }
+        };
+        // Without the message's CRC seed there is no telling a frame from noise
+        if MavMessage::default_message_from_id(message_id).is_err() {
+            return Candidate::NotAFrame;
+        }
+
+        let checksum_at = header_len + payload_len;
+        let len = checksum_at + CHECKSUM_LEN + signature_len;
+        if bytes.len() < len {
+            return Candidate::Incomplete;
+        }
+        let expected =
+            mavlink::calculate_crc(&bytes[1..checksum_at], MavMessage::extra_crc(message_id));
+        let received = u16::from_le_bytes([bytes[checksum_at], bytes[checksum_at + 1]]);
+        if expected != received {
+            return Candidate::BadCrc(message_id);
+        }
+        Candidate::Frame {
+            version,
+            header_len,
+            payload_len,
+            len,
+        }
+    }
+
+    fn take_garbage(&mut self) -> Option<FrameEvent> {
+        (self.garbage > 0).then(|| FrameEvent::Garbage(std::mem::take(&mut self.garbage)))
+    }
+
+    /// Drops the consumed bytes and appends the next read.
+    fn fill(&mut self) -> io::Result<()> {
+        self.buf.drain(..self.pos);
+        self.pos = 0;
+        let mut chunk = [0u8; READ_CHUNK];
+        let n = self.reader.read(&mut chunk)?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.buf.extend_from_slice(&chunk[..n]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use mavlink::ardupilotmega::*;
+
+    use super::*;
+
+    /// Hands out one chunk per read, like datagrams or serial reads.
+    struct Chunks(VecDeque<Vec<u8>>);
+
+    impl Read for Chunks {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some(chunk) = self.0.pop_front() else {
+                return Ok(0);
+            };
+            buf[..chunk.len()].copy_from_slice(&chunk);
+            Ok(chunk.len())
+        }
+    }
+
+    fn frame(version: MavlinkVersion, sequence: u8) -> Vec<u8> {
+        let header = MavHeader {
+            system_id: 1,
+            component_id: 2,
+            sequence,
+        };
+        let message = MavMessage::ATTITUDE(ATTITUDE_DATA {
+            roll: 0.5,
+            ..Default::default()
+        });
+        let mut bytes = Vec::new();
+        mavlink::write_versioned_msg(&mut bytes, version, header, &message).unwrap();
+        bytes
+    }
+
+    /// Every event until the stream ends, in a compact form.
+    fn events(chunks: Vec<Vec<u8>>) -> Vec<String> {
+        let mut framer = Framer::new(Chunks(chunks.into()));
+        let mut out = Vec::new();
+        loop {
+            match framer.next_event() {
+                Ok(FrameEvent::Message {
+                    header,
+                    message,
+                    len,
+                    ..
+                }) => out.push(format!(
+                    "{} seq {} len {}",
+                    message.message_name(),
+                    header.sequence,
+                    len
+                )),
+                Ok(FrameEvent::Garbage(n)) => out.push(format!("garbage {}", n)),
+                Ok(FrameEvent::BadCrc { message_id }) => out.push(format!("crc {}", message_id)),
+                Ok(FrameEvent::Invalid(_)) => out.push("invalid".to_string()),
+                Err(e) => {
+                    assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+                    return out;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reads_v1_and_v2_frames() {
+        let v1 = frame(MavlinkVersion::V1, 1);
+        let v2 = frame(MavlinkVersion::V2, 2);
+        let (v1_len, v2_len) = (v1.len(), v2.len());
+        assert_eq!(
+            events(vec![[v1, v2].concat()]),
+            [
+                format!("ATTITUDE seq 1 len {}", v1_len),
+                format!("ATTITUDE seq 2 len {}", v2_len),
+            ]
+        );
+    }
+
+    #[test]
+    fn joins_frames_split_across_reads() {
+        let bytes = frame(MavlinkVersion::V2, 7);
+        let (a, b) = bytes.split_at(5);
+        assert_eq!(events(vec![a.to_vec(), b.to_vec()]).len(), 1);
+    }
+
+    #[test]
+    fn counts_bytes_between_frames() {
+        let noise = b"hello\r\n".to_vec();
+        let stream = [
+            noise.clone(),
+            frame(MavlinkVersion::V2, 1),
+            noise,
+            frame(MavlinkVersion::V2, 2),
+        ]
+        .concat();
+        let events = events(vec![stream]);
+        assert_eq!(events[0], "garbage 7");
+        assert!(events[1].starts_with("ATTITUDE seq 1"));
+        assert_eq!(events[2], "garbage 7");
+        assert!(events[3].starts_with("ATTITUDE seq 2"));
+    }
+
+    #[test]
+    fn reports_bad_checksums_and_resynchronizes() {
+        let mut corrupt = frame(MavlinkVersion::V2, 1);
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 0xFF;
+        let corrupt_len = corrupt.len();
+        let events = events(vec![[corrupt, frame(MavlinkVersion::V2, 2)].concat()]);
+        assert_eq!(events[0], "crc 30");
+        // The rest of the corrupt frame is skipped as garbage
+        assert_eq!(events[1], format!("garbage {}", corrupt_len));
+        assert!(events[2].starts_with("ATTITUDE seq 2"));
+        assert_eq!(events.len(), 3);
+    }
+
+    #[test]
+    fn counts_one_bad_checksum_per_resync() {
+        let mut corrupt = frame(MavlinkVersion::V2, 1);
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 0xFF;
+        // Noise that starts like a MAVLink 1 ATTITUDE frame
+        let noise = [0xFE, 0x1C, 0x00, 0x01, 0x01, 0x1E, 0x00, 0x00];
+        let stream = [
+            corrupt.clone(),
+            noise.to_vec(),
+            vec![0u8; 40],
+            frame(MavlinkVersion::V2, 2),
+            corrupt,
+        ]
+        .concat();
+        let events = events(vec![stream]);
+        let crc_errors = events.iter().filter(|e| e.starts_with("crc")).count();
+        assert_eq!(crc_errors, 2, "{:?}", events);
+        assert!(events.iter().any(|e| e.starts_with("ATTITUDE seq 2")));
+    }
+
+    #[test]
+    fn reads_signed_frames() {
+        let mut signed = frame(MavlinkVersion::V2, 3);
+        signed[2] |= IFLAG_SIGNED;
+        // The flag is covered by the checksum, so recompute it
+        let checksum_at = signed.len() - CHECKSUM_LEN;
+        let crc = mavlink::calculate_crc(&signed[1..checksum_at], MavMessage::extra_crc(30));
+        signed[checksum_at..].copy_from_slice(&crc.to_le_bytes());
+        signed.extend_from_slice(&[0u8; SIGNATURE_LEN]);
+        let len = signed.len();
+        assert_eq!(
+            events(vec![signed]),
+            [format!("ATTITUDE seq 3 len {}", len)]
+        );
+    }
+
+    #[test]
+    fn reads_statustext_chunk_fields() {
+        // MAVLink 2 STATUSTEXT with the extension fields rust-mavlink skips
+        let mut payload = vec![MavSeverity::MAV_SEVERITY_INFO as u8];
+        payload.extend_from_slice(&[b'x'; 50]);
+        payload.extend_from_slice(&[0x34, 0x12, 2]);
+        let mut frame = vec![STX_V2, payload.len() as u8, 0, 0, 0, 1, 1, 253, 0, 0];
+        frame.extend_from_slice(&payload);
+        let crc = mavlink::calculate_crc(&frame[1..], MavMessage::extra_crc(253));
+        frame.extend_from_slice(&crc.to_le_bytes());
+        // The same text without them, trimmed the way MAVLink 2 senders do
+        let mut trimmed = Vec::new();
+        let (header, message) = (
+            MavHeader::default(),
+            MavMessage::STATUSTEXT(STATUSTEXT_DATA {
+                severity: MavSeverity::MAV_SEVERITY_INFO,
+                text: [0; 50],
+            }),
+        );
+        mavlink::write_versioned_msg(&mut trimmed, MavlinkVersion::V2, header, &message).unwrap();
+
+        let mut framer = Framer::new(Chunks(vec![frame, trimmed].into()));
+        let mut chunks = Vec::new();
+        while let Ok(event) = framer.next_event() {
+            if let FrameEvent::Message { text_chunk, .. } = event {
+                chunks.push(text_chunk.unwrap());
+            }
+        }
+        assert_eq!(
+            chunks,
+            [TextChunk { id: 0x1234, seq: 2 }, TextChunk::default()]
+        );
+    }
+
+    #[test]
+    fn reports_trailing_garbage_before_the_end() {
+        assert_eq!(
+            events(vec![vec![0x00, 0xFD, 0x01]]),
+            ["garbage 1", "garbage 2"]
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{bail, Result};
+use crossterm::event::{KeyCode, KeyEvent};
+
+/// Remappable actions of the live view and their default keys. An action
+/// keeps its meaning in every pane, e.g. `pin` pins the table row or, with
+/// the detail pane focused, the field.
+pub const LIVE_ACTIONS: &[(&str, KeyCode)] = &[
+    ("quit", KeyCode::Char('q')),
+    ("filter", KeyCode::Char('/')),
+    ("up", KeyCode::Char('k')),
+    ("down", KeyCode::Char('j')),
+    ("focus", KeyCode::Tab),
+    ("sort", KeyCode::Char('s')),
+    ("pause", KeyCode::Char(' ')),
+    ("tree", KeyCode::Char('T')),
+    ("dialect", KeyCode::Char('D')),
+    ("pin", KeyCode::Char('p')),
+    ("unpin_all", KeyCode::Char('P')),
+    ("hide", KeyCode::Char('x')),
+    ("unhide_all", KeyCode::Char('X')),
+    ("links", KeyCode::Char('l')),
+    ("throughput", KeyCode::Char('t')),
+    ("vehicles", KeyCode::Char('v')),
+    ("console", KeyCode::Char('c')),
+    ("alerts", KeyCode::Char('A')),
+    ("watch", KeyCode::Char('w')),
+    ("watch_panel", KeyCode::Char('W')),
+    ("copy", KeyCode::Char('y')),
+    ("save", KeyCode::Char('S')),
+    ("snapshot", KeyCode::Char('E')),
+    ("histogram", KeyCode::Char('h')),
+    ("humanize", KeyCode::Char('r')),
+    ("history_back", KeyCode::Char('[')),
+    ("history_forward", KeyCode::Char(']')),
+    ("history_back_10", KeyCode::Char('{')),
+    ("history_forward_10", KeyCode::Char('}')),
+    ("toggle", KeyCode::Enter),
+    ("collapse", KeyCode::Left),
+    ("expand", KeyCode::Right),
+    ("reset_watches", KeyCode::Char('R')),
+    ("newest", KeyCode::Char('G')),
+    ("severity_0", KeyCode::Char('0')),
+    ("severity_1", KeyCode::Char('1')),
+    ("severity_2", KeyCode::Char('2')),
+    ("severity_3", KeyCode::Char('3')),
+    ("severity_4", KeyCode::Char('4')),
+    ("severity_5", KeyCode::Char('5')),
+    ("severity_6", KeyCode::Char('6')),
+    ("severity_7", KeyCode::Char('7')),
+    ("plot_axes", KeyCode::Char('a')),
+    ("scroll_down", KeyCode::Char('d')),
+    ("scroll_up", KeyCode::Char('u')),
+];
+
+pub const REPLAY_ACTIONS: &[(&str, KeyCode)] = &[
+    ("quit", KeyCode::Char('q')),
+    ("filter", KeyCode::Char('/')),
+    ("up", KeyCode::Char('k')),
+    ("down", KeyCode::Char('j')),
+    ("first", KeyCode::Char('g')),
+    ("last", KeyCode::Char('G')),
+    ("source", KeyCode::Char('f')),
+    ("visual", KeyCode::Char('v')),
+    ("export", KeyCode::Char('w')),
+    ("copy", KeyCode::Char('y')),
+    ("save", KeyCode::Char('S')),
+    ("scroll_down", KeyCode::Char('d')),
+    ("scroll_up", KeyCode::Char('u')),
+];
+
+/// Keys that stay fixed aliases of the actions above or close panes and prompts
+const RESERVED: &[KeyCode] = &[
+    KeyCode::Esc,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Delete,
+];
+
+/// Translates remapped keys back to the built-in bindings, so the key
+/// handlers only ever see the defaults. A default key that was moved to
+/// another key stops working unless something else is mapped onto it.
+#[derive(Default)]
+pub struct Keymap {
+    map: HashMap<KeyCode, KeyCode>,
+}
+
+impl Keymap {
+    pub fn new(actions: &[(&str, KeyCode)], bindings: &BTreeMap<String, String>) -> Result<Self> {
+        let mut map = HashMap::new();
+        let mut bound: HashMap<KeyCode, &str> = HashMap::new();
+        for (action, key) in bindings {
+            let Some(&(_, default)) = actions.iter().find(|(name, _)| name == action) else {
+                let names: Vec<&str> = actions.iter().map(|(name, _)| *name).collect();
+                bail!(
+                    "Unknown action '{}' (expected one of {})",
+                    action,
+                    names.join(", ")
+                );
+            };
+            let key = parse_key(key)?;
+            if RESERVED.contains(&key) {
+                bail!(
+                    "Key '{}' is reserved and cannot be bound to {}",
+                    key,
+                    action
+                );
+            }
+            let owner = actions
+                .iter()
+                .find(|&&(name, default)| default == key && name != action);
+            if let Some(&(owner, _)) = owner.filter(|(name, _)| !bindings.contains_key(*name)) {
+                bail!(
+                    "Key '{}' already belongs to {}; bind {} to another key to use it for {}",
+                    key,
+                    owner,
+                    owner,
+                    action
+                );
+            }
+            if let Some(other) = bound.insert(key, action) {
+                bail!("Key '{}' is bound to both {} and {}", key, other, action);
+            }
+            map.entry(default).or_insert(KeyCode::Null);
+            map.insert(key, default);
+        }
+        Ok(Self { map })
+    }
+
+    pub fn apply(&self, key: KeyEvent) -> KeyEvent {
+        match self.map.get(&key.code) {
+            Some(&code) => KeyEvent { code, ..key },
+            None => key,
+        }
+    }
+}
+
+/// A single character, or a key name such as `space`, `tab`, `enter` or `pagedown`.
+fn parse_key(text: &str) -> Result<KeyCode> {
+    let mut chars = text.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(KeyCode::Char(c));
+    }
+    Ok(match text.to_lowercase().as_str() {
+        "space" => KeyCode::Char(' '),
+        "tab" => KeyCode::Tab,
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        _ => match text.strip_prefix(['f', 'F']).and_then(|n| n.parse().ok()) {
+            Some(n) => KeyCode::F(n),
+            None => bail!("Unknown key '{}'", text),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+
+    use super::*;
+
+    fn keymap(bindings: &[(&str, &str)]) -> Result<Keymap> {
+        let bindings = bindings
+            .iter()
+            .map(|(action, key)| (action.to_string(), key.to_string()))
+            .collect();
+        Keymap::new(LIVE_ACTIONS, &bindings)
+    }
+
+    fn apply(keymap: &Keymap, code: KeyCode) -> KeyCode {
+        keymap.apply(KeyEvent::new(code, KeyModifiers::NONE)).code
+    }
+
+    #[test]
+    fn moved_keys_reach_their_action() {
+        let keymap = keymap(&[
+            ("reset_watches", "z"),
+            ("collapse", "h"),
+            ("histogram", "f2"),
+        ])
+        .unwrap();
+        assert_eq!(apply(&keymap, KeyCode::Char('z')), KeyCode::Char('R'));
+        assert_eq!(apply(&keymap, KeyCode::Char('R')), KeyCode::Null);
+        assert_eq!(apply(&keymap, KeyCode::Char('h')), KeyCode::Left);
+        assert_eq!(apply(&keymap, KeyCode::F(2)), KeyCode::Char('h'));
+    }
+
+    #[test]
+    fn taking_a_default_key_requires_moving_its_action() {
+        let Err(err) = keymap(&[("collapse", "h")]) else {
+            panic!("h still opens the histogram");
+        };
+        assert!(err.to_string().contains("histogram"), "{}", err);
+        // Binding an action to its own default key is fine
+        assert!(keymap(&[("histogram", "h")]).is_ok());
+    }
+
+    #[test]
+    fn every_default_key_belongs_to_one_action() {
+        for (i, (name, key)) in LIVE_ACTIONS.iter().enumerate() {
+            assert!(
+                LIVE_ACTIONS[i + 1..].iter().all(|(_, other)| other != key),
+                "{} shares its key",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn reserved_keys_cannot_be_bound() {
+        assert!(keymap(&[("pin", "esc")]).is_err());
+        assert!(keymap(&[("pin", "down")]).is_err());
+        assert!(keymap(&[("pin", "enter"), ("toggle", "o")]).is_ok());
+    }
+}
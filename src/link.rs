@@ -0,0 +1,323 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::mavlink_io::{LinkError, LinkErrorKind};
+
+/// How far back, in sequence numbers, a packet still counts as late rather
+/// than as the start of a new gap
+const REORDER_WINDOW: u8 = 32;
+/// Silence after which a sender is assumed to have restarted or the link to
+/// have dropped out, so its sequence numbers say nothing about loss
+const RESET_SECS: f64 = 5.0;
+/// Sequence steps seen before the sender's rate is trusted for wrap estimates
+const MIN_RATE_STEPS: u64 = 16;
+
+/// Sequence-number bookkeeping for one MAVLink link (a sys_id:comp_id sender).
+///
+/// A packet up to `REORDER_WINDOW` behind the newest one is late (if its number
+/// was counted as lost) or a duplicate (if it was already seen); anything else
+/// moves forward. The 8-bit counter wraps, so a forward gap is corrected by the
+/// number of whole wraps the time since the previous packet suggests at the
+/// sender's average rate since counting started; packets arriving in bursts
+/// leave that average alone. After `RESET_SECS` of silence counting starts over.
+#[derive(Default, Clone, Serialize)]
+pub struct SequenceStats {
+    pub received: u64,
+    pub lost: u64,
+    pub duplicated: u64,
+    pub out_of_order: u64,
+    /// Times counting started over after a long silence
+    pub resets: u64,
+    #[serde(skip)]
+    last: Option<(u8, DateTime<Utc>)>,
+    /// When counting (re)started
+    #[serde(skip)]
+    since: Option<DateTime<Utc>>,
+    /// Sequence steps moved forward since then, wraps included
+    #[serde(skip)]
+    steps: u64,
+    /// Numbers inside the reorder window counted as lost
+    #[serde(skip)]
+    missing: VecDeque<u8>,
+    /// Numbers inside the reorder window already received
+    #[serde(skip)]
+    seen: VecDeque<u8>,
+}
+
+impl SequenceStats {
+    pub fn update(&mut self, sequence: u8, at: DateTime<Utc>) {
+        self.received += 1;
+
+        let Some((last, last_at)) = self.last else {
+            self.restart(sequence, at);
+            return;
+        };
+        let elapsed = (at - last_at).num_microseconds().unwrap_or(0).max(0) as f64 / 1e6;
+        if elapsed > RESET_SECS {
+            self.resets += 1;
+            self.restart(sequence, at);
+            return;
+        }
+
+        let behind = last.wrapping_sub(sequence);
+        if behind <= REORDER_WINDOW {
+            if let Some(i) = self.missing.iter().position(|&s| s == sequence) {
+                // A late packet was already counted as lost when the gap opened
+                self.missing.remove(i);
+                self.out_of_order += 1;
+                self.lost -= 1;
+                self.seen.push_back(sequence);
+                return;
+            }
+            if behind == 0 || self.seen.contains(&sequence) {
+                self.duplicated += 1;
+            } else {
+                // From before counting (re)started
+                self.out_of_order += 1;
+            }
+            return;
+        }
+
+        let gap = sequence.wrapping_sub(last);
+        let mut skipped = u64::from(gap - 1);
+        if let Some(interval) = self.interval(last_at).filter(|&i| i > 0.0) {
+            // Frames the sender would have sent in the meantime, minus this one
+            let expected = elapsed / interval - 1.0;
+            let wraps = ((expected - skipped as f64) / 256.0).round().max(0.0);
+            skipped += wraps as u64 * 256;
+        }
+        self.steps += skipped + 1;
+        self.lost += skipped;
+        for n in 1..gap {
+            self.missing.push_back(last.wrapping_add(n));
+        }
+        self.seen.push_back(sequence);
+        self.last = Some((sequence, at));
+        self.missing
+            .retain(|&s| sequence.wrapping_sub(s) <= REORDER_WINDOW);
+        self.seen
+            .retain(|&s| sequence.wrapping_sub(s) <= REORDER_WINDOW);
+    }
+
+    /// Average seconds per sequence step up to `until`, once enough are known.
+    fn interval(&self, until: DateTime<Utc>) -> Option<f64> {
+        let since = self.since?;
+        (self.steps >= MIN_RATE_STEPS).then(|| {
+            (until - since).num_microseconds().unwrap_or(0) as f64 / 1e6 / self.steps as f64
+        })
+    }
+
+    fn restart(&mut self, sequence: u8, at: DateTime<Utc>) {
+        self.last = Some((sequence, at));
+        self.since = Some(at);
+        self.steps = 0;
+        self.missing.clear();
+        self.seen.clear();
+        self.seen.push_back(sequence);
+    }
+
+    pub fn loss_percent(&self) -> f64 {
+        let unique = self.received - self.duplicated;
+        let expected = unique + self.lost;
+        if expected == 0 {
+            0.0
+        } else {
+            self.lost as f64 / expected as f64 * 100.0
+        }
+    }
+}
+
+/// One of the connections given on the command line.
+#[derive(Clone)]
+pub struct Connection {
+    /// Short label for the table and filters; the URI unless one was given
+    pub name: String,
+    pub uri: String,
+    pub health: LinkHealth,
+}
+
+impl Connection {
+    /// Parses `URI` or `NAME=URI`, e.g. `radio=serial:/dev/ttyUSB0:57600`.
+    pub fn parse(spec: &str) -> Self {
+        let (name, uri) = match spec.split_once('=') {
+            Some((name, uri)) if !name.is_empty() && !name.contains(':') => (name, uri),
+            _ => (spec, spec),
+        };
+        Self {
+            name: name.to_string(),
+            uri: uri.to_string(),
+            health: LinkHealth::default(),
+        }
+    }
+}
+
+/// Connection-wide receive counters: throughput and decode errors.
+#[derive(Default, Clone)]
+pub struct LinkHealth {
+    /// (receive time, frame length) of every frame inside the rate window
+    window: VecDeque<(Instant, usize)>,
+    /// (receive time, length) of every discarded run inside the rate window
+    discarded: VecDeque<(Instant, usize)>,
+    pub msgs_per_sec: f64,
+    /// Every byte read, whether or not it was part of a valid frame
+    pub bytes_per_sec: f64,
+    pub total_bytes: u64,
+    /// Bytes outside any valid frame, including frames with a bad CRC
+    pub discarded_bytes: u64,
+    pub crc_errors: u64,
+    pub parse_errors: u64,
+    pub io_errors: u64,
+    /// Description and time of the most recent receive error
+    pub last_error: Option<(String, Instant)>,
+    pub last_good: Option<Instant>,
+}
+
+impl LinkHealth {
+    pub fn on_frame(&mut self, at: Instant, frame_len: usize) {
+        self.window.push_back((at, frame_len));
+        self.total_bytes += frame_len as u64;
+        self.last_good = Some(at);
+    }
+
+    pub fn on_error(&mut self, error: &LinkError) {
+        match error.kind {
+            // Line noise between frames is routine, so not the last error
+            LinkErrorKind::Garbage(bytes) => {
+                self.discarded.push_back((error.at, bytes));
+                self.discarded_bytes += bytes as u64;
+                self.total_bytes += bytes as u64;
+                return;
+            }
+            LinkErrorKind::Crc => self.crc_errors += 1,
+            LinkErrorKind::Parse => self.parse_errors += 1,
+            LinkErrorKind::Io => self.io_errors += 1,
+        }
+        self.last_error = Some((error.description.clone(), error.at));
+    }
+
+    pub fn tick(&mut self, now: Instant, window_secs: f64) {
+        for window in [&mut self.window, &mut self.discarded] {
+            while let Some(&(front, _)) = window.front() {
+                if now.duration_since(front).as_secs_f64() > window_secs {
+                    window.pop_front();
+                } else {
+                    break;
+                }
+            }
+        }
+        self.msgs_per_sec = self.window.len() as f64 / window_secs;
+        let bytes: usize = self
+            .window
+            .iter()
+            .chain(&self.discarded)
+            .map(|&(_, len)| len)
+            .sum();
+        self.bytes_per_sec = bytes as f64 / window_secs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, Utc};
+
+    use super::SequenceStats;
+
+    /// Feeds `sequences` arriving 10 ms apart.
+    fn feed(sequences: &[u8]) -> SequenceStats {
+        let mut stats = SequenceStats::default();
+        for (i, &sequence) in sequences.iter().enumerate() {
+            stats.update(sequence, at(i as i64 * 10));
+        }
+        stats
+    }
+
+    fn at(ms: i64) -> DateTime<Utc> {
+        DateTime::UNIX_EPOCH + Duration::milliseconds(ms)
+    }
+
+    #[test]
+    fn counts_a_clean_stream_across_the_wrap() {
+        let sequences: Vec<u8> = (0..600).map(|n| (n % 256) as u8).collect();
+        let stats = feed(&sequences);
+        assert_eq!(stats.received, 600);
+        assert_eq!(stats.lost, 0);
+        assert_eq!(stats.duplicated, 0);
+        assert_eq!(stats.out_of_order, 0);
+    }
+
+    #[test]
+    fn counts_gaps_as_loss_across_the_wrap() {
+        let stats = feed(&[250, 251, 254, 255, 3, 4]);
+        assert_eq!(stats.lost, 2 + 3);
+        assert_eq!(stats.loss_percent(), 5.0 / 11.0 * 100.0);
+    }
+
+    #[test]
+    fn counts_duplicates() {
+        let stats = feed(&[1, 2, 2, 3, 2]);
+        assert_eq!(stats.duplicated, 2);
+        assert_eq!(stats.lost, 0);
+    }
+
+    #[test]
+    fn late_packets_give_back_their_loss() {
+        let stats = feed(&[1, 2, 4, 5, 3, 6]);
+        assert_eq!(stats.out_of_order, 1);
+        assert_eq!(stats.lost, 0);
+        // Late again: now a duplicate, not a second reorder
+        let stats = feed(&[1, 2, 4, 3, 3]);
+        assert_eq!(
+            (stats.out_of_order, stats.duplicated, stats.lost),
+            (1, 1, 0)
+        );
+    }
+
+    #[test]
+    fn counts_burst_loss_beyond_half_the_range() {
+        let mut stats = SequenceStats::default();
+        for n in 0..100u8 {
+            stats.update(n, at(i64::from(n) * 10));
+        }
+        // 200 frames lost: 99 -> 44 (300 mod 256), 2 s later at 100 Hz
+        stats.update(44, at(990 + 2010));
+        assert_eq!(stats.lost, 200);
+        assert_eq!(stats.out_of_order, 0);
+
+        // More than a whole wrap lost: 300 frames
+        stats.update(45, at(3010));
+        stats.update(90, at(3010 + 3010));
+        assert_eq!(stats.lost, 200 + 300);
+    }
+
+    #[test]
+    fn bursts_do_not_look_like_wraps() {
+        // Three packets 0.1 ms apart every 50 ms, with one lost in between
+        let mut stats = SequenceStats::default();
+        let mut sequence = 0u8;
+        for burst in 0..100i64 {
+            for n in 0..3 {
+                let us = burst * 50_000 + n * 100;
+                stats.update(sequence, DateTime::UNIX_EPOCH + Duration::microseconds(us));
+                sequence = sequence.wrapping_add(1);
+            }
+            sequence = sequence.wrapping_add(1);
+        }
+        assert_eq!(stats.lost, 99);
+    }
+
+    #[test]
+    fn starts_over_after_a_restart() {
+        let mut stats = SequenceStats::default();
+        for n in 0..50u8 {
+            stats.update(n + 100, at(i64::from(n) * 10));
+        }
+        stats.update(0, at(500 + 10_000));
+        stats.update(1, at(500 + 10_010));
+        assert_eq!(stats.resets, 1);
+        assert_eq!(stats.lost, 0);
+        assert_eq!(stats.received, 52);
+    }
+}
@@ -1,17 +1,39 @@
+mod alert;
 mod app;
+mod bench;
+mod config;
+mod console;
+mod fields;
+mod filter;
+mod framing;
+mod keymap;
+mod link;
 mod mavlink_io;
+mod mouse;
 mod record;
 mod replay;
+mod schema;
+mod share;
+mod state;
+mod stats;
+mod theme;
+mod transport;
 mod ui;
+mod units;
+mod vehicle;
+mod watch;
 
 use std::fs::File;
-use std::io;
+use std::io::{self, Write};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
+use chrono::Utc;
 use clap::{Parser, Subcommand};
-use crossterm::event::{self, Event, KeyEventKind};
+use crossterm::event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyEventKind};
 use crossterm::execute;
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
@@ -21,91 +43,297 @@ use ratatui::prelude::*;
 use simplelog::{Config as LogConfig, WriteLogger};
 
 use app::App;
+use link::Connection;
+use mavlink_io::LinkEvent;
 use record::{RecordFilter, Recorder};
 
+const DEFAULT_URI: &str = "udpin:0.0.0.0:14550";
+/// How long to wait for a key before drawing the next frame
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Most time spent taking messages off the channel per frame, so the view
+/// keeps redrawing when they arrive faster than they are handled
+const INGEST_BUDGET: Duration = Duration::from_millis(40);
+
 #[derive(Parser)]
 #[command(name = "mavshark", version, about = "MAVLink message inspector")]
 struct Cli {
-    /// Connection URI (e.g. udpin:0.0.0.0:14550, tcpout:127.0.0.1:5760, serial:/dev/ttyUSB0:57600)
-    #[arg(default_value = "udpin:0.0.0.0:14550")]
-    uri: String,
+    /// Connection URIs (e.g. udpin:0.0.0.0:14550, tcpout:127.0.0.1:5760, serial:/dev/ttyUSB0:57600), each optionally named as NAME=URI [default: udpin:0.0.0.0:14550]
+    uris: Vec<String>,
+
+    /// Connection profile from the config file
+    #[arg(short, long, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Config file to use instead of config.toml in the config directory
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<String>,
 
     /// Send heartbeats with this system ID
     #[arg(long)]
     heartbeat_sys_id: Option<u8>,
 
     /// Heartbeat component ID (used with --heartbeat-sys-id)
-    #[arg(long, default_value_t = 1)]
-    heartbeat_comp_id: u8,
+    #[arg(long)]
+    heartbeat_comp_id: Option<u8>,
 
-    /// Log file path
-    #[arg(long, default_value = "mavshark.log")]
-    log_file: String,
+    /// Log file path [default: mavshark.log]
+    #[arg(long)]
+    log_file: Option<String>,
 
     /// Record messages to a JSON Lines file
     #[arg(long)]
     record: Option<String>,
 
-    /// Comma-separated message names or numeric IDs to record (default: all)
+    /// Message names or numeric IDs to record, comma-separated, or a filter expression (default: all)
     #[arg(long)]
     record_filter: Option<String>,
 
+    /// Replace the --record file if it already exists
+    #[arg(long)]
+    force: bool,
+
+    /// Seconds of history shown in the plot panel for pinned fields [default: 30]
+    #[arg(long)]
+    plot_window: Option<f64>,
+
+    /// Alert rule as a filter expression, e.g. "HEARTBEAT and age > 3" (repeatable)
+    #[arg(long = "alert", value_name = "RULE")]
+    alerts: Vec<String>,
+
+    /// File with one alert rule per line
+    #[arg(long)]
+    alert_file: Option<String>,
+
+    /// Ring the terminal bell when an alert triggers
+    #[arg(long)]
+    alert_bell: bool,
+
+    /// Field to show in the watch panel, as [sys:comp] MESSAGE.field (repeatable)
+    #[arg(long = "watch", value_name = "FIELD")]
+    watches: Vec<String>,
+
+    /// Number of past instances kept per message for the history view [default: 200]
+    #[arg(long)]
+    history: Option<usize>,
+
+    /// Count each (sys, comp, message) once across all connections instead of per connection
+    #[arg(long, overrides_with = "no_merge_links")]
+    merge_links: bool,
+
+    /// Count messages per connection even if the config merges them
+    #[arg(long, overrides_with = "merge_links")]
+    no_merge_links: bool,
+
+    /// Color theme: dark, light, high-contrast or mono [default: mono if NO_COLOR is set, else dark]
+    #[arg(long, global = true, value_name = "NAME")]
+    theme: Option<String>,
+
+    /// Use the mouse in the TUI even if the config turns it off
+    #[arg(long, global = true, overrides_with = "no_mouse")]
+    mouse: bool,
+
+    /// Leave the mouse to the terminal (e.g. for text selection) instead of the TUI
+    #[arg(long, global = true, overrides_with = "mouse")]
+    no_mouse: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Open a recorded JSON Lines file in the replay TUI
+    /// Open recorded JSON Lines files in the replay TUI, merged by timestamp
     Replay {
+        /// Paths to the JSON Lines recording files
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+    /// Print rate, loss and size statistics for a recorded JSON Lines file
+    Stats {
         /// Path to the JSON Lines recording file
         file: String,
+
+        /// Print the report as JSON instead of tables
+        #[arg(long)]
+        json: bool,
+    },
+    /// Feed synthetic traffic through the live view and report whether it keeps up
+    Bench {
+        /// Messages per second to send
+        #[arg(long, default_value_t = 50_000)]
+        rate: u32,
+
+        /// Seconds to send for
+        #[arg(long, default_value_t = 5.0)]
+        secs: f64,
+
+        /// Number of simulated vehicles, each sending a dozen message types
+        #[arg(long, default_value_t = 10)]
+        vehicles: u8,
     },
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    // Replay mode — no logging, no connection
-    if let Some(Commands::Replay { file }) = cli.command {
-        return replay::run_replay(&file);
+    // Offline modes — no config, no logging, no connection
+    match cli.command {
+        Some(Commands::Stats { file, json }) => return stats::run_stats(&file, json),
+        Some(Commands::Bench {
+            rate,
+            secs,
+            vehicles,
+        }) => return bench::run_bench(rate, secs, vehicles),
+        _ => {}
+    }
+
+    let config = config::Config::load(cli.config.as_deref())?;
+    let defaults = &config.defaults;
+    let mouse = switch(cli.mouse, cli.no_mouse)
+        .or(defaults.mouse)
+        .unwrap_or(true);
+    let split_percent = defaults.split.unwrap_or(mouse::DEFAULT_SPLIT_PERCENT);
+    let theme = theme::Theme::resolve(cli.theme.as_deref().or(defaults.theme.as_deref()))?;
+
+    if let Some(Commands::Replay { files }) = cli.command {
+        let options = replay::ReplayOptions {
+            mouse,
+            split_percent,
+            keymap: keymap::Keymap::new(keymap::REPLAY_ACTIONS, &config.keys.replay)
+                .map_err(|e| e.context("Invalid [keys.replay] in config"))?,
+            theme,
+        };
+        return replay::run_replay(&files, options);
+    }
+
+    // Command-line flags, then the profile, then the config defaults
+    let default_profile = config::Profile::default();
+    let profile = match &cli.profile {
+        Some(name) => config.profile(name)?,
+        None => &default_profile,
+    };
+    let mut uris = cli.uris.clone();
+    if uris.is_empty() {
+        uris.extend(profile.uri.iter().chain(&profile.uris).cloned());
+    }
+    if uris.is_empty() {
+        uris.push(DEFAULT_URI.to_string());
+    }
+    let connections: Vec<Connection> = uris.iter().map(|spec| Connection::parse(spec)).collect();
+    let heartbeat_sys_id = cli.heartbeat_sys_id.or(profile.heartbeat_sys_id);
+    let heartbeat_comp_id = cli
+        .heartbeat_comp_id
+        .or(profile.heartbeat_comp_id)
+        .unwrap_or(1);
+    let record = cli.record.clone().or_else(|| profile.record.clone());
+    let record_filter = cli
+        .record_filter
+        .clone()
+        .or_else(|| profile.record_filter.clone());
+    let keymap = keymap::Keymap::new(keymap::LIVE_ACTIONS, &config.keys.live)
+        .map_err(|e| e.context("Invalid [keys.live] in config"))?;
+    if let Some(secs) = cli.plot_window {
+        config::check_plot_window(secs)?;
     }
 
     // File logging (keeps logs out of the TUI)
-    let log_file = File::create(&cli.log_file)?;
+    let log_path = cli
+        .log_file
+        .clone()
+        .or_else(|| defaults.log_file.clone())
+        .unwrap_or_else(|| "mavshark.log".to_string());
+    let log_file = File::create(&log_path)?;
     WriteLogger::init(LevelFilter::Info, LogConfig::default(), log_file)?;
-    log::info!("mavshark starting, connecting to {}", cli.uri);
+    for connection in &connections {
+        log::info!(
+            "mavshark starting, connecting to {} as {}",
+            connection.uri,
+            connection.name
+        );
+    }
+
+    // Rules and watches given on the command line replace the profile's
+    let alert_rules = if cli.alert_file.is_some() || !cli.alerts.is_empty() {
+        let mut rules = match &cli.alert_file {
+            Some(path) => alert::load_rules(path)?,
+            None => Vec::new(),
+        };
+        for rule in &cli.alerts {
+            rules.push(alert::AlertRule::parse(rule)?);
+        }
+        rules
+    } else {
+        profile
+            .alerts
+            .iter()
+            .map(|rule| alert::AlertRule::parse(rule))
+            .collect::<Result<Vec<_>>>()?
+    };
+    let watch_specs = if cli.watches.is_empty() {
+        &profile.watches
+    } else {
+        &cli.watches
+    };
+    let watches = watch_specs
+        .iter()
+        .map(|spec| watch::Watch::parse(spec))
+        .collect::<Result<Vec<_>>>()?;
 
     // Connect MAVLink
-    let conn = mavlink::connect::<mavlink::ardupilotmega::MavMessage>(&cli.uri)
-        .map_err(|e| anyhow::anyhow!("Failed to connect to {}: {}", cli.uri, e))?;
-    let conn = Arc::new(conn);
+    let conns = connections
+        .iter()
+        .map(|c| {
+            transport::open(&c.uri)
+                .map_err(|e| anyhow::anyhow!("Failed to connect to {}: {}", c.uri, e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let (sources, writers): (Vec<_>, Vec<_>) = conns.into_iter().unzip();
 
     let stop = Arc::new(AtomicBool::new(false));
     let (tx, rx) = std::sync::mpsc::channel();
 
-    // Listener thread
-    let listener_conn = conn.clone();
-    let listener_stop = stop.clone();
-    std::thread::spawn(move || {
-        mavlink_io::listener_loop(listener_conn, tx, listener_stop);
-    });
-
-    // Heartbeat thread (optional)
-    let heartbeat_handle = cli.heartbeat_sys_id.map(|sys_id| {
-        let hb_conn = conn.clone();
-        let hb_stop = stop.clone();
-        let comp_id = cli.heartbeat_comp_id;
+    // Listener thread per connection, all feeding the same channel
+    for (link, source) in sources.into_iter().enumerate() {
+        let listener_tx = tx.clone();
+        let listener_stop = stop.clone();
         std::thread::spawn(move || {
-            mavlink_io::heartbeat_loop(hb_conn, sys_id, comp_id, hb_stop);
-        })
-    });
+            mavlink_io::listener_loop(link, source, listener_tx, listener_stop);
+        });
+    }
+    drop(tx);
+
+    // Heartbeat threads (optional), one per connection
+    let heartbeat_handles: Vec<_> = match heartbeat_sys_id {
+        Some(sys_id) => writers
+            .iter()
+            .map(|writer| {
+                let hb_writer = writer.clone();
+                let hb_stop = stop.clone();
+                std::thread::spawn(move || {
+                    mavlink_io::heartbeat_loop(hb_writer, sys_id, heartbeat_comp_id, hb_stop);
+                })
+            })
+            .collect(),
+        None => Vec::new(),
+    };
 
     // Set up recorder (optional)
-    let mut recorder = match &cli.record {
+    let mut recorder = match &record {
         Some(path) => {
-            let filter = RecordFilter::new(cli.record_filter.as_deref());
-            let r = Recorder::new(path, filter)?;
+            let filter = RecordFilter::new(record_filter.as_deref())?;
+            let mut r = if cli.force {
+                Recorder::new(path, filter)?
+            } else {
+                Recorder::create_new(path, filter).map_err(|e| {
+                    e.context(format!(
+                        "Cannot record to {} (--force replaces an existing file)",
+                        path
+                    ))
+                })?
+            };
+            if connections.len() > 1 {
+                r.link_names = Some(connections.iter().map(|c| c.name.clone()).collect());
+            }
             log::info!("Recording to {}", path);
             Some(r)
         }
@@ -116,7 +344,7 @@ fn main() -> Result<()> {
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |info| {
         let _ = disable_raw_mode();
-        let _ = execute!(io::stdout(), LeaveAlternateScreen);
+        let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture);
         original_hook(info);
     }));
 
@@ -124,54 +352,124 @@ fn main() -> Result<()> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
+    if mouse {
+        execute!(stdout, EnableMouseCapture)?;
+    }
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     // Run app
-    let heartbeat_info = cli.heartbeat_sys_id.map(|s| (s, cli.heartbeat_comp_id));
-    let mut app = App::new(cli.uri.clone(), heartbeat_info);
+    let heartbeat_info = heartbeat_sys_id.map(|s| (s, heartbeat_comp_id));
+    let mut app = App::new(connections, heartbeat_info);
+    app.merge_links = switch(cli.merge_links, cli.no_merge_links)
+        .or(defaults.merge_links)
+        .unwrap_or(false);
+    app.plot_window_secs = cli
+        .plot_window
+        .or(defaults.plot_window)
+        .unwrap_or(app::DEFAULT_PLOT_WINDOW_SECS);
+    app.history_len = cli
+        .history
+        .or(defaults.history)
+        .unwrap_or(app::DEFAULT_HISTORY_LEN)
+        .max(1);
+    app.hz_window_secs = defaults.hz_window.unwrap_or(app::DEFAULT_HZ_WINDOW_SECS);
+    app.split.percent = split_percent;
+    if let Some(sort) = defaults.sort_mode()? {
+        app.sort_mode = sort;
+    }
+    app.tree_view = defaults.tree.unwrap_or(false);
+    app.humanize = defaults.humanize.unwrap_or(false);
+    app.panel = defaults.panel()?;
+    app.keymap = keymap;
+    app.theme = theme;
+    app.saved = state::SavedState::load();
+    app.alerts.rules = alert_rules;
+    app.alerts.bell = cli.alert_bell;
+    if !watches.is_empty() {
+        app.watches = watches;
+        app.panel = Some(app::Panel::Watch);
+    }
     let result = run_app(&mut terminal, &mut app, rx, &mut recorder);
 
     // Cleanup
     stop.store(true, Ordering::Relaxed);
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
     terminal.show_cursor()?;
 
-    if let Some(h) = heartbeat_handle {
+    for h in heartbeat_handles {
         let _ = h.join();
     }
 
     result
 }
 
+/// A `--name` / `--no-name` flag pair; None when neither was given.
+fn switch(on: bool, off: bool) -> Option<bool> {
+    (on || off).then_some(on)
+}
+
 fn run_app(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app: &mut App,
-    rx: std::sync::mpsc::Receiver<mavlink_io::ReceivedMessage>,
+    rx: std::sync::mpsc::Receiver<mavlink_io::LinkEvent>,
     recorder: &mut Option<Recorder>,
 ) -> Result<()> {
     loop {
-        while let Ok(msg) = rx.try_recv() {
+        ingest(app, &rx, recorder);
+        app.tick();
+
+        let mut ring = false;
+        for alert in app.alerts.take_new() {
             if let Some(rec) = recorder.as_mut() {
-                rec.record(&msg);
+                let _ = rec.annotate(&alert.describe(), alert.time.with_timezone(&Utc));
             }
-            app.on_message(msg);
+            ring |= alert.triggered && app.alerts.bell;
         }
-
-        if let Some(rec) = recorder.as_mut() {
-            rec.flush();
+        if ring {
+            terminal.backend_mut().write_all(b"\x07")?;
+            Write::flush(terminal.backend_mut())?;
         }
 
-        app.tick();
+        app.refresh();
         terminal.draw(|f| ui::draw(f, app))?;
 
-        if event::poll(std::time::Duration::from_millis(50))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press && app.on_key(key) {
-                    return Ok(());
+        if event::poll(POLL_INTERVAL)? {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press && app.on_key(key) => {
+                    return Ok(())
                 }
+                Event::Mouse(mouse) => app.on_mouse(mouse),
+                _ => {}
             }
         }
     }
 }
+
+/// Hands queued link events to the app and the recorder, for at most
+/// `INGEST_BUDGET`. Returns the number of events taken.
+fn ingest(app: &mut App, rx: &Receiver<LinkEvent>, recorder: &mut Option<Recorder>) -> usize {
+    let deadline = Instant::now() + INGEST_BUDGET;
+    let mut count = 0;
+    while let Ok(event) = rx.try_recv() {
+        if let (LinkEvent::Message(msg), Some(rec)) = (&event, recorder.as_mut()) {
+            rec.record(msg);
+        }
+        app.on_event(event);
+        count += 1;
+        // Reading the clock costs about as much as a cheap message
+        if count % 64 == 0 && Instant::now() >= deadline {
+            break;
+        }
+    }
+
+    if let Some(rec) = recorder.as_mut() {
+        rec.flush();
+    }
+    count
+}
@@ -1,57 +1,141 @@
+use std::io;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Utc};
 use mavlink::ardupilotmega::*;
-use mavlink::{MavConnection, MavHeader, Message};
+use mavlink::{MAVLinkV2MessageRaw, MavHeader, Message};
+
+use crate::framing::{FrameEvent, Framer, TextChunk};
+use crate::transport::{ByteSource, Writer};
 
 pub struct ReceivedMessage {
+    /// Index of the connection it arrived on
+    pub link: usize,
     pub header: MavHeader,
     pub message: MavMessage,
     pub received_at: Instant,
+    /// Wall-clock arrival time
+    pub timestamp: DateTime<Utc>,
+    /// Bytes the frame took on the wire, signature included
+    pub frame_len: usize,
+    /// Chunk fields of a STATUSTEXT
+    pub text_chunk: Option<TextChunk>,
+}
+
+pub enum LinkErrorKind {
+    /// This many bytes were not part of any valid frame
+    Garbage(usize),
+    /// A frame of a known message had a bad checksum
+    Crc,
+    /// A frame passed the CRC check but could not be decoded
+    Parse,
+    Io,
+}
+
+pub struct LinkError {
+    pub link: usize,
+    pub kind: LinkErrorKind,
+    pub description: String,
+    pub at: Instant,
+}
+
+/// Everything the listener reports to the UI thread.
+// Messages are the hot path; boxing them only to shrink the rare error variant
+// would cost an allocation per frame.
+#[allow(clippy::large_enum_variant)]
+pub enum LinkEvent {
+    Message(ReceivedMessage),
+    Error(LinkError),
+}
+
+/// Size in bytes of `message` encoded as an unsigned MAVLink 2 frame, for
+/// traffic that never went over a wire.
+pub fn frame_len(message: &MavMessage) -> usize {
+    let mut raw = MAVLinkV2MessageRaw::new();
+    raw.serialize_message(MavHeader::default(), message);
+    raw.raw_bytes().len()
 }
 
 pub fn listener_loop(
-    conn: Arc<Box<dyn MavConnection<MavMessage> + Sync + Send>>,
-    tx: std::sync::mpsc::Sender<ReceivedMessage>,
+    link: usize,
+    source: ByteSource,
+    tx: std::sync::mpsc::Sender<LinkEvent>,
     stop: Arc<AtomicBool>,
 ) {
+    let mut framer = Framer::new(source);
     while !stop.load(Ordering::Relaxed) {
-        match conn.recv() {
-            Ok((header, message)) => {
-                log::info!(
-                    "recv {} from {}:{}",
+        let error = |kind, description| {
+            LinkEvent::Error(LinkError {
+                link,
+                kind,
+                description,
+                at: Instant::now(),
+            })
+        };
+        let event = match framer.next_event() {
+            Ok(FrameEvent::Message {
+                header,
+                message,
+                len,
+                text_chunk,
+            }) => {
+                log::trace!(
+                    "recv {} from {}:{} on link {}",
                     message.message_name(),
                     header.system_id,
-                    header.component_id
+                    header.component_id,
+                    link
                 );
-                let msg = ReceivedMessage {
+                LinkEvent::Message(ReceivedMessage {
+                    link,
                     header,
                     message,
                     received_at: Instant::now(),
-                };
-                if tx.send(msg).is_err() {
-                    break;
-                }
+                    timestamp: Utc::now(),
+                    frame_len: len,
+                    text_chunk,
+                })
+            }
+            Ok(FrameEvent::Garbage(bytes)) => error(
+                LinkErrorKind::Garbage(bytes),
+                format!("{} bytes of non-MAVLink data", bytes),
+            ),
+            Ok(FrameEvent::BadCrc { message_id }) => error(
+                LinkErrorKind::Crc,
+                format!("Bad CRC on a frame with message id {}", message_id),
+            ),
+            Ok(FrameEvent::Invalid(description)) => error(LinkErrorKind::Parse, description),
+            // Read timeouts only let the loop check `stop`
+            Err(e) if is_timeout(&e) => continue,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                log::info!("Link {} closed", link);
+                let _ = tx.send(error(LinkErrorKind::Io, "Connection closed".to_string()));
+                break;
             }
             Err(e) => {
-                log::error!("MAVLink recv error: {}", e);
-                if stop.load(Ordering::Relaxed) {
-                    break;
-                }
+                log::error!("MAVLink recv error on link {}: {}", link, e);
+                // Back off so a failing device does not spin
                 std::thread::sleep(Duration::from_millis(100));
+                error(LinkErrorKind::Io, e.to_string())
             }
+        };
+        if tx.send(event).is_err() {
+            break;
         }
     }
-    log::info!("Listener stopped");
+    log::info!("Listener {} stopped", link);
 }
 
-pub fn heartbeat_loop(
-    conn: Arc<Box<dyn MavConnection<MavMessage> + Sync + Send>>,
-    sys_id: u8,
-    comp_id: u8,
-    stop: Arc<AtomicBool>,
-) {
+fn is_timeout(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+pub fn heartbeat_loop(writer: Arc<Writer>, sys_id: u8, comp_id: u8, stop: Arc<AtomicBool>) {
     let header = MavHeader {
         system_id: sys_id,
         component_id: comp_id,
@@ -68,7 +152,7 @@ pub fn heartbeat_loop(
 
     log::info!("Sending heartbeats as {}:{}", sys_id, comp_id);
     while !stop.load(Ordering::Relaxed) {
-        if let Err(e) = conn.send(&header, &msg) {
+        if let Err(e) = writer.send(&header, &msg) {
             log::error!("Heartbeat send error: {}", e);
         }
         std::thread::sleep(Duration::from_secs(1));
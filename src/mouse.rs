@@ -0,0 +1,87 @@
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+
+pub const DEFAULT_SPLIT_PERCENT: u16 = 45;
+/// Narrowest and widest the table may be, by drag or by `split` in the config
+pub const MIN_SPLIT_PERCENT: u16 = 10;
+pub const MAX_SPLIT_PERCENT: u16 = 90;
+/// Lines scrolled per wheel step
+pub const WHEEL_LINES: usize = 3;
+/// Header line plus its bottom margin in the message tables
+pub const TABLE_HEADER_HEIGHT: u16 = 2;
+
+/// Where the table and detail pane were drawn last frame, for mapping mouse
+/// positions back to them.
+#[derive(Default, Clone, Copy)]
+pub struct HitAreas {
+    /// Area split between the table and the detail pane
+    pub main: Rect,
+    pub table: Rect,
+    pub detail: Rect,
+}
+
+impl HitAreas {
+    /// Row of the table body under `y`, counted from the first visible row.
+    pub fn table_row(&self, column: u16, row: u16) -> Option<usize> {
+        let body_top = self.table.y + 1 + TABLE_HEADER_HEIGHT;
+        let inside = self.table.contains(Position::new(column, row))
+            && row >= body_top
+            && row < self.table.bottom().saturating_sub(1);
+        inside.then(|| (row - body_top) as usize)
+    }
+
+    /// Whether `(column, row)` is on the header line of the table.
+    pub fn on_table_header(&self, column: u16, row: u16) -> bool {
+        row == self.table.y + 1
+            && column > self.table.x
+            && column < self.table.right().saturating_sub(1)
+    }
+
+    pub fn in_detail(&self, column: u16, row: u16) -> bool {
+        self.detail.contains(Position::new(column, row))
+    }
+}
+
+/// The draggable divider between the table and the detail pane.
+pub struct Split {
+    /// Width of the table as a percentage of the main area
+    pub percent: u16,
+    dragging: bool,
+}
+
+impl Default for Split {
+    fn default() -> Self {
+        Self {
+            percent: DEFAULT_SPLIT_PERCENT,
+            dragging: false,
+        }
+    }
+}
+
+impl Split {
+    /// Starts, follows or ends a drag of the divider, which sits on the
+    /// table's right border and the detail pane's left border. Returns
+    /// true if the event was part of a drag.
+    pub fn on_mouse(&mut self, event: &MouseEvent, areas: &HitAreas) -> bool {
+        let on_divider = event.row >= areas.main.y
+            && event.row < areas.main.bottom()
+            && (event.column + 1 == areas.table.right() || event.column == areas.detail.x);
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) if on_divider => {
+                self.dragging = true;
+            }
+            MouseEventKind::Drag(MouseButton::Left) if self.dragging => {
+                if areas.main.width > 0 {
+                    let offset = event.column.saturating_sub(areas.main.x) as u32;
+                    let percent = (offset * 100 / areas.main.width as u32) as u16;
+                    self.percent = percent.clamp(MIN_SPLIT_PERCENT, MAX_SPLIT_PERCENT);
+                }
+            }
+            MouseEventKind::Up(MouseButton::Left) if self.dragging => {
+                self.dragging = false;
+            }
+            _ => return false,
+        }
+        true
+    }
+}
@@ -8,7 +8,10 @@ use chrono::{DateTime, Utc};
 use mavlink::Message;
 use serde::{Deserialize, Serialize};
 
+use crate::fields;
+use crate::filter::{self, Expr, Subject, Value};
 use crate::mavlink_io::ReceivedMessage;
+use crate::share;
 
 #[derive(Serialize, Deserialize)]
 pub struct RecordedHeader {
@@ -24,35 +27,66 @@ pub struct RecordedMessage {
     pub message_id: u32,
     pub message_name: String,
     pub message: String,
+    /// Encoded MAVLink 2 frame size; absent in recordings made before it was added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_len: Option<usize>,
+    /// Connection the message arrived on; only written when there were several.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
 }
 
+/// A note written into a recording between messages, such as an alert.
+#[derive(Serialize, Deserialize)]
+pub struct Annotation {
+    pub timestamp: DateTime<Utc>,
+    pub annotation: String,
+}
+
+/// Which messages `--record-filter` lets through: either the original
+/// comma-separated list of names and IDs, or a filter expression.
 pub struct RecordFilter {
     names: HashSet<String>,
     ids: HashSet<u32>,
+    expr: Option<Expr>,
     accept_all: bool,
 }
 
 impl RecordFilter {
-    pub fn new(spec: Option<&str>) -> Self {
+    pub fn all() -> Self {
+        Self {
+            names: HashSet::new(),
+            ids: HashSet::new(),
+            expr: None,
+            accept_all: true,
+        }
+    }
+
+    pub fn new(spec: Option<&str>) -> Result<Self> {
         let spec = match spec {
-            Some(s) if !s.is_empty() => s,
-            _ => {
-                return Self {
-                    names: HashSet::new(),
-                    ids: HashSet::new(),
-                    accept_all: true,
-                }
-            }
+            Some(s) if !s.trim().is_empty() => s,
+            _ => return Ok(Self::all()),
         };
 
+        let is_list = spec.split(',').all(|token| {
+            let token = token.trim();
+            !token.is_empty() && token.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+        if !is_list {
+            let expr =
+                Expr::parse(spec).map_err(|e| anyhow::anyhow!("Invalid record filter: {}", e))?;
+            return Ok(Self {
+                names: HashSet::new(),
+                ids: HashSet::new(),
+                accept_all: expr.is_none(),
+                expr,
+            });
+        }
+
         let mut names = HashSet::new();
         let mut ids = HashSet::new();
 
         for token in spec.split(',') {
             let token = token.trim();
-            if token.is_empty() {
-                continue;
-            }
             if let Ok(id) = token.parse::<u32>() {
                 ids.insert(id);
             } else {
@@ -60,48 +94,130 @@ impl RecordFilter {
             }
         }
 
-        Self {
+        Ok(Self {
             names,
             ids,
+            expr: None,
             accept_all: false,
-        }
+        })
     }
 
-    pub fn matches(&self, msg: &mavlink::ardupilotmega::MavMessage) -> bool {
+    /// `link` is the name of the connection `msg` came in on, if recorded.
+    pub fn matches(&self, msg: &ReceivedMessage, link: Option<&str>) -> bool {
         if self.accept_all {
             return true;
         }
-        if self.ids.contains(&msg.message_id()) {
+        if let Some(expr) = &self.expr {
+            return expr.matches(&Incoming { msg, link });
+        }
+        if self.ids.contains(&msg.message.message_id()) {
             return true;
         }
-        if self.names.contains(&msg.message_name().to_uppercase()) {
+        if self
+            .names
+            .contains(&msg.message.message_name().to_uppercase())
+        {
             return true;
         }
         false
     }
 }
 
+/// A message about to be recorded, with the name of its connection.
+struct Incoming<'a> {
+    msg: &'a ReceivedMessage,
+    link: Option<&'a str>,
+}
+
+impl Subject for Incoming<'_> {
+    fn name(&self) -> &str {
+        self.msg.message.message_name()
+    }
+
+    fn system_id(&self) -> u8 {
+        self.msg.header.system_id
+    }
+
+    fn component_id(&self) -> u8 {
+        self.msg.header.component_id
+    }
+
+    fn message_id(&self) -> u32 {
+        self.msg.message.message_id()
+    }
+
+    fn link(&self) -> Option<String> {
+        self.link.map(str::to_string)
+    }
+
+    fn field(&self, path: &str) -> Option<Value> {
+        filter::row_field(&fields::field_rows(&self.msg.message), path)
+    }
+}
+
+impl Subject for RecordedMessage {
+    fn name(&self) -> &str {
+        &self.message_name
+    }
+
+    fn system_id(&self) -> u8 {
+        self.header.system_id
+    }
+
+    fn component_id(&self) -> u8 {
+        self.header.component_id
+    }
+
+    fn message_id(&self) -> u32 {
+        self.message_id
+    }
+
+    fn link(&self) -> Option<String> {
+        self.link.clone()
+    }
+
+    fn field(&self, path: &str) -> Option<Value> {
+        filter::debug_field(&self.message, path)
+    }
+}
+
 pub struct Recorder {
     writer: BufWriter<File>,
     filter: RecordFilter,
+    /// Names to tag messages with by connection, when recording several
+    pub link_names: Option<Vec<String>>,
 }
 
 impl Recorder {
+    /// Creates the recording at `path`, replacing any file already there.
     pub fn new(path: &str, filter: RecordFilter) -> Result<Self> {
-        let file = File::create(path)?;
-        Ok(Self {
+        Ok(Self::with_file(File::create(path)?, filter))
+    }
+
+    /// Like `new`, but fails instead of replacing an existing file.
+    pub fn create_new(path: &str, filter: RecordFilter) -> Result<Self> {
+        Ok(Self::with_file(share::create_new(path)?, filter))
+    }
+
+    fn with_file(file: File, filter: RecordFilter) -> Self {
+        Self {
             writer: BufWriter::new(file),
             filter,
-        })
+            link_names: None,
+        }
     }
 
     pub fn record(&mut self, msg: &ReceivedMessage) {
-        if !self.filter.matches(&msg.message) {
+        let link = self
+            .link_names
+            .as_ref()
+            .and_then(|names| names.get(msg.link));
+        if !self.filter.matches(msg, link.map(String::as_str)) {
             return;
         }
 
         let recorded = RecordedMessage {
-            timestamp: Utc::now(),
+            timestamp: msg.timestamp,
             header: RecordedHeader {
                 system_id: msg.header.system_id,
                 component_id: msg.header.component_id,
@@ -110,22 +226,46 @@ impl Recorder {
             message_id: msg.message.message_id(),
             message_name: msg.message.message_name().to_string(),
             message: format!("{:#?}", msg.message),
+            frame_len: Some(msg.frame_len),
+            link: link.cloned(),
         };
 
-        if let Ok(json) = serde_json::to_string(&recorded) {
-            let _ = writeln!(self.writer, "{}", json);
-        }
+        let _ = self.write(&recorded);
+    }
+
+    pub fn write(&mut self, recorded: &RecordedMessage) -> Result<()> {
+        let json = serde_json::to_string(recorded)?;
+        writeln!(self.writer, "{}", json)?;
+        Ok(())
+    }
+
+    /// Writes a note made at `timestamp` regardless of the filter.
+    pub fn annotate(&mut self, text: &str, timestamp: DateTime<Utc>) -> Result<()> {
+        let annotation = Annotation {
+            timestamp,
+            annotation: text.to_string(),
+        };
+        writeln!(self.writer, "{}", serde_json::to_string(&annotation)?)?;
+        Ok(())
     }
 
     pub fn flush(&mut self) {
         let _ = self.writer.flush();
     }
+
+    /// Flushes the file, reporting any write error.
+    pub fn finish(mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
 }
 
-pub fn load_recording(path: &Path) -> Result<Vec<RecordedMessage>> {
+/// Reads the messages of a recording and the annotations between them.
+pub fn load_recording(path: &Path) -> Result<(Vec<RecordedMessage>, Vec<Annotation>)> {
     let file = File::open(path)?;
     let reader = BufReader::new(file);
     let mut messages = Vec::new();
+    let mut annotations = Vec::new();
 
     for line in reader.lines() {
         let line = line?;
@@ -134,9 +274,12 @@ pub fn load_recording(path: &Path) -> Result<Vec<RecordedMessage>> {
         }
         match serde_json::from_str::<RecordedMessage>(&line) {
             Ok(msg) => messages.push(msg),
-            Err(e) => log::warn!("Skipping invalid record: {}", e),
+            Err(e) => match serde_json::from_str::<Annotation>(&line) {
+                Ok(annotation) => annotations.push(annotation),
+                Err(_) => log::warn!("Skipping invalid record: {}", e),
+            },
         }
     }
 
-    Ok(messages)
+    Ok((messages, annotations))
 }
@@ -2,7 +2,10 @@ use std::io;
 use std::path::Path;
 
 use anyhow::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+    MouseButton, MouseEvent, MouseEventKind,
+};
 use crossterm::execute;
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
@@ -10,35 +13,82 @@ use crossterm::terminal::{
 use ratatui::prelude::*;
 use ratatui::widgets::TableState;
 
-use crate::record::{load_recording, RecordedMessage};
+use crate::filter::Expr;
+use crate::keymap::Keymap;
+use crate::mouse::{HitAreas, Split, WHEEL_LINES};
+use crate::record::{load_recording, Annotation, RecordFilter, RecordedMessage, Recorder};
+use crate::share;
+use crate::theme::Theme;
 
 pub struct ReplayApp {
-    pub file_path: String,
+    /// Display labels of the loaded files, indexed by `message_sources`.
+    pub sources: Vec<String>,
     pub messages: Vec<RecordedMessage>,
+    pub message_sources: Vec<usize>,
+    /// Notes such as alerts written into the recordings, oldest first
+    pub annotations: Vec<Annotation>,
+    pub source_filter: Option<usize>,
     pub selected: usize,
     pub detail_scroll: usize,
     pub filter: String,
     pub filter_active: bool,
+    filter_expr: Option<Expr>,
+    pub filter_error: Option<String>,
     pub filtered_indices: Vec<usize>,
     pub table_state: TableState,
+    /// Row (position in `filtered_indices`) where the visual selection started
+    pub visual_anchor: Option<usize>,
+    pub export_active: bool,
+    pub export_path: String,
+    /// Export only rows matching the current filter instead of every message in the range
+    pub export_filtered: bool,
+    /// Save-message prompt: open flag and file name
+    pub save_active: bool,
+    pub save_path: String,
+    pub status: Option<String>,
+    /// Table/detail areas of the last frame, for mouse hit testing
+    pub hit: HitAreas,
+    pub split: Split,
+    pub keymap: Keymap,
+    pub theme: Theme,
 }
 
 impl ReplayApp {
-    pub fn new(file_path: String, messages: Vec<RecordedMessage>) -> Self {
+    pub fn new(
+        sources: Vec<String>,
+        messages: Vec<RecordedMessage>,
+        message_sources: Vec<usize>,
+    ) -> Self {
         let filtered_indices: Vec<usize> = (0..messages.len()).collect();
         let mut table_state = TableState::default();
         if !filtered_indices.is_empty() {
             table_state.select(Some(0));
         }
         Self {
-            file_path,
+            sources,
             messages,
+            message_sources,
+            annotations: Vec::new(),
+            source_filter: None,
             selected: 0,
             detail_scroll: 0,
             filter: String::new(),
             filter_active: false,
+            filter_expr: None,
+            filter_error: None,
             filtered_indices,
             table_state,
+            visual_anchor: None,
+            export_active: false,
+            export_path: String::new(),
+            export_filtered: true,
+            save_active: false,
+            save_path: String::new(),
+            status: None,
+            hit: HitAreas::default(),
+            split: Split::default(),
+            keymap: Keymap::default(),
+            theme: Theme::dark(),
         }
     }
 
@@ -47,68 +97,133 @@ impl ReplayApp {
             return true;
         }
 
+        self.status = None;
+
+        if self.export_active {
+            match key.code {
+                KeyCode::Esc => {
+                    self.export_active = false;
+                }
+                KeyCode::Enter => {
+                    self.export_active = false;
+                    self.export();
+                }
+                KeyCode::Tab => {
+                    self.export_filtered = !self.export_filtered;
+                }
+                KeyCode::Backspace => {
+                    self.export_path.pop();
+                }
+                KeyCode::Char(c) => {
+                    self.export_path.push(c);
+                }
+                _ => {}
+            }
+            return false;
+        }
+
+        if self.save_active {
+            match key.code {
+                KeyCode::Esc => {
+                    self.save_active = false;
+                }
+                KeyCode::Enter => {
+                    self.save_active = false;
+                    self.save_message();
+                }
+                KeyCode::Tab => share::toggle_extension(&mut self.save_path),
+                KeyCode::Backspace => {
+                    self.save_path.pop();
+                }
+                KeyCode::Char(c) => {
+                    self.save_path.push(c);
+                }
+                _ => {}
+            }
+            return false;
+        }
+
         if self.filter_active {
             match key.code {
                 KeyCode::Esc => {
                     self.filter.clear();
                     self.filter_active = false;
-                    self.rebuild_filter();
+                    self.parse_filter();
                 }
                 KeyCode::Enter => {
                     self.filter_active = false;
                 }
                 KeyCode::Backspace => {
                     self.filter.pop();
-                    self.rebuild_filter();
+                    self.parse_filter();
                 }
                 KeyCode::Char(c) => {
                     self.filter.push(c);
-                    self.rebuild_filter();
+                    self.parse_filter();
                 }
                 _ => {}
             }
             return false;
         }
 
+        let key = self.keymap.apply(key);
         match key.code {
             KeyCode::Char('q') => return true,
             KeyCode::Char('/') => {
                 self.filter_active = true;
             }
-            KeyCode::Up | KeyCode::Char('k') => {
-                if self.selected > 0 {
-                    self.selected -= 1;
-                    self.detail_scroll = 0;
-                }
-                self.table_state.select(Some(self.selected));
-            }
-            KeyCode::Down | KeyCode::Char('j') => {
-                if !self.filtered_indices.is_empty()
-                    && self.selected < self.filtered_indices.len() - 1
-                {
-                    self.selected += 1;
-                    self.detail_scroll = 0;
-                }
-                self.table_state.select(Some(self.selected));
-            }
+            KeyCode::Up | KeyCode::Char('k') => self.select_row(self.selected.saturating_sub(1)),
+            KeyCode::Down | KeyCode::Char('j') => self.select_row(self.selected + 1),
             KeyCode::Char('g') => {
                 self.selected = 0;
                 self.detail_scroll = 0;
                 self.table_state.select(Some(0));
             }
-            KeyCode::Char('G') => {
-                if !self.filtered_indices.is_empty() {
-                    self.selected = self.filtered_indices.len() - 1;
-                    self.detail_scroll = 0;
-                    self.table_state.select(Some(self.selected));
-                }
+            KeyCode::Char('G') if !self.filtered_indices.is_empty() => {
+                self.selected = self.filtered_indices.len() - 1;
+                self.detail_scroll = 0;
+                self.table_state.select(Some(self.selected));
             }
-            KeyCode::Esc => {
-                if !self.filter.is_empty() {
-                    self.filter.clear();
-                    self.rebuild_filter();
+            KeyCode::Char('f') if self.sources.len() > 1 => {
+                self.source_filter = match self.source_filter {
+                    None => Some(0),
+                    Some(i) if i + 1 < self.sources.len() => Some(i + 1),
+                    Some(_) => None,
+                };
+                self.rebuild_filter();
+            }
+            KeyCode::Char('v') if !self.filtered_indices.is_empty() => {
+                self.visual_anchor = match self.visual_anchor {
+                    Some(_) => None,
+                    None => Some(self.selected),
+                };
+            }
+            KeyCode::Char('w') if !self.filtered_indices.is_empty() => {
+                let (start, _) = self.selection_range();
+                let first = &self.messages[self.filtered_indices[start]];
+                self.export_path = format!("export-{}.jsonl", first.timestamp.format("%H%M%S"));
+                self.export_active = true;
+            }
+            KeyCode::Char('y') => self.copy_message(),
+            KeyCode::Char('S') => {
+                if let Some(msg) = self.selected_message() {
+                    self.save_path = format!(
+                        "{}-{}-{}-{}.json",
+                        msg.message_name,
+                        msg.header.system_id,
+                        msg.header.component_id,
+                        msg.timestamp.format("%Y%m%d-%H%M%S")
+                    );
+                    self.save_active = true;
                 }
             }
+            KeyCode::Esc if self.visual_anchor.is_some() => {
+                self.visual_anchor = None;
+            }
+            KeyCode::Esc if !self.filter.is_empty() => {
+                self.filter.clear();
+                self.parse_filter();
+            }
             KeyCode::PageDown | KeyCode::Char('d') => {
                 self.detail_scroll = self.detail_scroll.saturating_add(10);
             }
@@ -120,24 +235,75 @@ impl ReplayApp {
         false
     }
 
+    /// Moves the table cursor to `row` if it exists.
+    fn select_row(&mut self, row: usize) {
+        if row < self.filtered_indices.len() && row != self.selected {
+            self.selected = row;
+            self.detail_scroll = 0;
+        }
+        if !self.filtered_indices.is_empty() {
+            self.table_state.select(Some(self.selected));
+        }
+    }
+
+    pub fn on_mouse(&mut self, event: MouseEvent) {
+        if self.export_active || self.save_active || self.split.on_mouse(&event, &self.hit) {
+            return;
+        }
+        let (column, row) = (event.column, event.row);
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(visible) = self.hit.table_row(column, row) {
+                    self.select_row(self.table_state.offset() + visible);
+                }
+            }
+            MouseEventKind::ScrollDown if self.hit.in_detail(column, row) => {
+                self.detail_scroll = self.detail_scroll.saturating_add(WHEEL_LINES);
+            }
+            MouseEventKind::ScrollUp if self.hit.in_detail(column, row) => {
+                self.detail_scroll = self.detail_scroll.saturating_sub(WHEEL_LINES);
+            }
+            MouseEventKind::ScrollDown if self.hit.table_row(column, row).is_some() => {
+                self.select_row(self.selected + 1);
+            }
+            MouseEventKind::ScrollUp if self.hit.table_row(column, row).is_some() => {
+                self.select_row(self.selected.saturating_sub(1));
+            }
+            _ => {}
+        }
+    }
+
+    fn parse_filter(&mut self) {
+        match Expr::parse(&self.filter) {
+            Ok(expr) => {
+                self.filter_expr = expr;
+                self.filter_error = None;
+            }
+            Err(e) => self.filter_error = Some(e.to_string()),
+        }
+        self.rebuild_filter();
+    }
+
     fn rebuild_filter(&mut self) {
-        let filter_upper = self.filter.to_uppercase();
         self.filtered_indices = self
             .messages
             .iter()
             .enumerate()
+            .filter(|&(i, _)| {
+                self.source_filter
+                    .is_none_or(|source| self.message_sources[i] == source)
+            })
             .filter(|(_, m)| {
-                if self.filter.is_empty() {
-                    true
-                } else {
-                    m.message_name.to_uppercase().contains(&filter_upper)
-                        || format!("{}:{}", m.header.system_id, m.header.component_id)
-                            .contains(&self.filter)
-                }
+                self.filter_expr
+                    .as_ref()
+                    .is_none_or(|expr| expr.matches(*m))
             })
             .map(|(i, _)| i)
             .collect();
 
+        // Selection rows refer to positions in the old filtered list
+        self.visual_anchor = None;
+
         if self.selected >= self.filtered_indices.len() {
             self.selected = self.filtered_indices.len().saturating_sub(1);
         }
@@ -153,45 +319,408 @@ impl ReplayApp {
             .get(self.selected)
             .and_then(|&idx| self.messages.get(idx))
     }
+
+    /// Inclusive range of filtered rows covered by the visual selection, or
+    /// every row when there is no selection.
+    pub fn selection_range(&self) -> (usize, usize) {
+        match self.visual_anchor {
+            Some(anchor) => (anchor.min(self.selected), anchor.max(self.selected)),
+            None => (0, self.filtered_indices.len().saturating_sub(1)),
+        }
+    }
+
+    pub fn in_selection(&self, row: usize) -> bool {
+        self.visual_anchor.is_some() && {
+            let (start, end) = self.selection_range();
+            (start..=end).contains(&row)
+        }
+    }
+
+    /// Message indices to export for the current selection: the filtered rows,
+    /// or every message of the shown source in their time range.
+    pub fn export_indices(&self) -> Vec<usize> {
+        if self.filtered_indices.is_empty() {
+            return Vec::new();
+        }
+        let (start, end) = self.selection_range();
+        if self.export_filtered {
+            self.filtered_indices[start..=end].to_vec()
+        } else {
+            (self.filtered_indices[start]..=self.filtered_indices[end])
+                .filter(|&i| {
+                    self.source_filter
+                        .is_none_or(|source| self.message_sources[i] == source)
+                })
+                .collect()
+        }
+    }
+
+    /// Writes the messages at `indices` to a new recording, with the
+    /// annotations made between the first and the last of them.
+    fn write_export(&self, path: &str, indices: &[usize]) -> Result<()> {
+        let mut rec = Recorder::create_new(path, RecordFilter::all())?;
+        let (Some(&first), Some(&last)) = (indices.first(), indices.last()) else {
+            return rec.finish();
+        };
+        let range = self.messages[first].timestamp..=self.messages[last].timestamp;
+        let mut notes = self
+            .annotations
+            .iter()
+            .filter(|note| range.contains(&note.timestamp))
+            .peekable();
+        for &idx in indices {
+            let msg = &self.messages[idx];
+            while let Some(note) = notes.next_if(|note| note.timestamp < msg.timestamp) {
+                rec.annotate(&note.annotation, note.timestamp)?;
+            }
+            rec.write(msg)?;
+        }
+        for note in notes {
+            rec.annotate(&note.annotation, note.timestamp)?;
+        }
+        rec.finish()
+    }
+
+    fn export(&mut self) {
+        let indices = self.export_indices();
+        let result = self.write_export(&self.export_path, &indices);
+
+        self.status = Some(match result {
+            Ok(()) => {
+                self.visual_anchor = None;
+                format!("Exported {} msgs to {}", indices.len(), self.export_path)
+            }
+            Err(e) => format!("Export to {} failed: {}", self.export_path, e),
+        });
+    }
+
+    /// The selected message as plain text, as in the detail pane.
+    fn selected_text(&self) -> Option<String> {
+        let msg = self.selected_message()?;
+        let via = match &msg.link {
+            Some(link) => format!(" via {}", link),
+            None => String::new(),
+        };
+        Some(format!(
+            "{} [{}:{}]{} at {}\n{}\n",
+            msg.message_name,
+            msg.header.system_id,
+            msg.header.component_id,
+            via,
+            msg.timestamp.format("%Y-%m-%d %H:%M:%S%.3f"),
+            msg.message
+        ))
+    }
+
+    fn copy_message(&mut self) {
+        let (Some(msg), Some(text)) = (self.selected_message(), self.selected_text()) else {
+            return;
+        };
+        self.status = Some(match share::copy_to_clipboard(&text) {
+            Ok(()) => format!(
+                "Copied {} [{}:{}] to the clipboard",
+                msg.message_name, msg.header.system_id, msg.header.component_id
+            ),
+            Err(e) => format!("Copy failed: {}", e),
+        });
+    }
+
+    /// Writes the selected message to `save_path`, as a recording line's
+    /// JSON or as text.
+    fn save_message(&mut self) {
+        let (Some(msg), Some(text)) = (self.selected_message(), self.selected_text()) else {
+            return;
+        };
+        self.status = Some(match share::save(&self.save_path, &text, msg) {
+            Ok(()) => format!("Saved {} to {}", msg.message_name, self.save_path),
+            Err(e) => format!("Save to {} failed: {}", self.save_path, e),
+        });
+    }
+
+    /// Annotations made after the message on filtered `row` and before the
+    /// next row's; the first row also takes any from before it.
+    pub fn row_annotations(&self, row: usize) -> &[Annotation] {
+        let at = |row: usize| {
+            self.filtered_indices
+                .get(row)
+                .map(|&idx| self.messages[idx].timestamp)
+        };
+        let Some(time) = at(row) else {
+            return &[];
+        };
+        let start = match row {
+            0 => 0,
+            _ => self.annotations.partition_point(|a| a.timestamp < time),
+        };
+        let end = match at(row + 1) {
+            Some(next) => self.annotations.partition_point(|a| a.timestamp < next),
+            None => self.annotations.len(),
+        };
+        &self.annotations[start..end.max(start)]
+    }
+
+    pub fn source_label(&self, idx: usize) -> &str {
+        &self.sources[self.message_sources[idx]]
+    }
+
+    pub fn source_filter_label(&self) -> &str {
+        match self.source_filter {
+            Some(source) => &self.sources[source],
+            None => "all",
+        }
+    }
 }
 
-pub fn run_replay(file: &str) -> Result<()> {
-    let path = Path::new(file);
-    let messages = load_recording(path)?;
+/// Loads every file and merges them into one timeline ordered by timestamp.
+/// Messages with equal timestamps keep the order of the files on the command line.
+/// Files without messages are left out and named in the status line.
+fn load_merged(files: &[String]) -> Result<ReplayApp> {
+    let mut loaded = Vec::new();
+    let mut skipped = Vec::new();
+    let mut annotations = Vec::new();
+    for file in files {
+        let (messages, notes) = load_recording(Path::new(file))?;
+        annotations.extend(notes);
+        if messages.is_empty() {
+            skipped.push(file.clone());
+        } else {
+            loaded.push((file.clone(), messages));
+        }
+    }
+    if loaded.is_empty() {
+        anyhow::bail!("No messages found in {}", files.join(", "));
+    }
 
-    if messages.is_empty() {
-        anyhow::bail!("No messages found in {}", file);
+    let paths: Vec<&str> = loaded.iter().map(|(file, _)| file.as_str()).collect();
+    let sources = source_labels(&paths);
+    let mut tagged = Vec::new();
+    for (source, (_, messages)) in loaded.into_iter().enumerate() {
+        tagged.extend(messages.into_iter().map(|m| (source, m)));
     }
 
-    let mut app = ReplayApp::new(file.to_string(), messages);
+    tagged.sort_by_key(|(_, m)| m.timestamp);
+    let (message_sources, messages) = tagged.into_iter().unzip();
+    let mut app = ReplayApp::new(sources, messages, message_sources);
+    annotations.sort_by_key(|a| a.timestamp);
+    app.annotations = annotations;
+    if !skipped.is_empty() {
+        app.status = Some(format!("Skipped empty {}", skipped.join(", ")));
+    }
+    Ok(app)
+}
+
+/// Labels each path by its shortest trailing run of components that no other
+/// path ends with, so `radio/flight.jsonl` and `companion/flight.jsonl` stay
+/// apart while distinct file names are shown alone.
+fn source_labels(paths: &[&str]) -> Vec<String> {
+    let components: Vec<Vec<String>> = paths
+        .iter()
+        .map(|path| {
+            Path::new(path)
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .collect()
+        })
+        .collect();
+    let suffix = |parts: &[String], len: usize| parts[parts.len().saturating_sub(len)..].to_vec();
+
+    components
+        .iter()
+        .enumerate()
+        .map(|(i, parts)| {
+            let unique_len = (1..=parts.len()).find(|&len| {
+                let own = suffix(parts, len);
+                components
+                    .iter()
+                    .enumerate()
+                    .all(|(j, other)| j == i || suffix(other, len) != own)
+            });
+            match unique_len {
+                Some(len) => Path::new(&suffix(parts, len).iter().collect::<std::path::PathBuf>())
+                    .to_string_lossy()
+                    .into_owned(),
+                None => paths[i].to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Terminal settings for the replay view, from the config file and flags.
+pub struct ReplayOptions {
+    pub mouse: bool,
+    pub split_percent: u16,
+    pub keymap: Keymap,
+    pub theme: Theme,
+}
+
+pub fn run_replay(files: &[String], options: ReplayOptions) -> Result<()> {
+    let mut app = load_merged(files)?;
+    app.split.percent = options.split_percent;
+    app.keymap = options.keymap;
+    app.theme = options.theme;
+    let mouse = options.mouse;
 
     // Restore terminal on panic
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |info| {
         let _ = disable_raw_mode();
-        let _ = execute!(io::stdout(), LeaveAlternateScreen);
+        let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture);
         original_hook(info);
     }));
 
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
+    if mouse {
+        execute!(stdout, EnableMouseCapture)?;
+    }
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     loop {
         terminal.draw(|f| crate::ui::draw_replay(f, &mut app))?;
 
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press && app.on_key(key) {
-                break;
-            }
+        match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press && app.on_key(key) => break,
+            Event::Mouse(mouse) => app.on_mouse(mouse),
+            _ => {}
         }
     }
 
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
     terminal.show_cursor()?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, Utc};
+
+    use super::*;
+    use crate::record::RecordedHeader;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::UNIX_EPOCH + Duration::seconds(secs)
+    }
+
+    fn message(secs: i64) -> RecordedMessage {
+        RecordedMessage {
+            timestamp: at(secs),
+            header: RecordedHeader {
+                system_id: 1,
+                component_id: 1,
+                sequence: 0,
+            },
+            message_id: 0,
+            message_name: "HEARTBEAT".to_string(),
+            message: String::new(),
+            frame_len: None,
+            link: None,
+        }
+    }
+
+    #[test]
+    fn labels_are_file_names_when_distinct() {
+        assert_eq!(
+            source_labels(&["logs/a.jsonl", "/tmp/b.jsonl"]),
+            ["a.jsonl", "b.jsonl"]
+        );
+    }
+
+    #[test]
+    fn labels_grow_until_unique() {
+        assert_eq!(
+            source_labels(&[
+                "radio/flight.jsonl",
+                "companion/flight.jsonl",
+                "other.jsonl"
+            ]),
+            [
+                "radio/flight.jsonl",
+                "companion/flight.jsonl",
+                "other.jsonl"
+            ]
+        );
+        assert_eq!(
+            source_labels(&["a/x/f.jsonl", "b/x/f.jsonl"]),
+            ["a/x/f.jsonl", "b/x/f.jsonl"]
+        );
+    }
+
+    #[test]
+    fn identical_paths_keep_the_full_path() {
+        assert_eq!(
+            source_labels(&["f.jsonl", "f.jsonl"]),
+            ["f.jsonl", "f.jsonl"]
+        );
+    }
+
+    #[test]
+    fn annotations_follow_the_row_before_them() {
+        let messages = vec![message(10), message(20), message(30)];
+        let mut app = ReplayApp::new(vec!["f.jsonl".to_string()], messages, vec![0, 0, 0]);
+        app.annotations = [5, 12, 30, 31]
+            .map(|secs| Annotation {
+                timestamp: at(secs),
+                annotation: secs.to_string(),
+            })
+            .into();
+        let notes = |app: &ReplayApp, row| -> Vec<String> {
+            app.row_annotations(row)
+                .iter()
+                .map(|a| a.annotation.clone())
+                .collect()
+        };
+        assert_eq!(notes(&app, 0), ["5", "12"]);
+        assert!(notes(&app, 1).is_empty());
+        assert_eq!(notes(&app, 2), ["30", "31"]);
+
+        // Hidden rows pass theirs on to the row before
+        app.filtered_indices = vec![0, 2];
+        assert_eq!(notes(&app, 0), ["5", "12"]);
+        assert_eq!(notes(&app, 1), ["30", "31"]);
+        app.filtered_indices = vec![1];
+        assert_eq!(notes(&app, 0), ["5", "12", "30", "31"]);
+    }
+
+    #[test]
+    fn exports_keep_to_the_source_and_carry_annotations() {
+        let messages = (10..16).map(message).collect();
+        let mut app = ReplayApp::new(
+            vec!["a.jsonl".to_string(), "b.jsonl".to_string()],
+            messages,
+            vec![0, 1, 0, 1, 0, 1],
+        );
+        app.annotations = [5, 11, 12, 14, 20]
+            .map(|secs| Annotation {
+                timestamp: at(secs),
+                annotation: secs.to_string(),
+            })
+            .into();
+        app.export_filtered = false;
+        app.source_filter = Some(0);
+        app.rebuild_filter();
+        app.visual_anchor = Some(0);
+        app.selected = 1;
+        assert_eq!(app.export_indices(), [0, 2]);
+        app.source_filter = Some(1);
+        app.rebuild_filter();
+        assert_eq!(app.export_indices(), [1, 3, 5]);
+
+        let path =
+            std::env::temp_dir().join(format!("mavshark-{}-export.jsonl", std::process::id()));
+        let path = path.to_str().unwrap();
+        app.write_export(path, &[0, 2, 4]).unwrap();
+        let (exported, notes) = load_recording(Path::new(path)).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(exported.len(), 3);
+        let notes: Vec<String> = notes.into_iter().map(|n| n.annotation).collect();
+        assert_eq!(notes, ["11", "12", "14"]);
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+
+/// The XML definitions the `ardupilotmega` dialect is generated from, in
+/// include order. Vendored from the mavlink crate so descriptions and units
+/// are available at runtime.
+const DEFINITIONS: [(&str, &str); 7] = [
+    ("minimal.xml", include_str!("../definitions/minimal.xml")),
+    ("standard.xml", include_str!("../definitions/standard.xml")),
+    ("common.xml", include_str!("../definitions/common.xml")),
+    ("uAvionix.xml", include_str!("../definitions/uAvionix.xml")),
+    ("icarous.xml", include_str!("../definitions/icarous.xml")),
+    (
+        "cubepilot.xml",
+        include_str!("../definitions/cubepilot.xml"),
+    ),
+    (
+        "ardupilotmega.xml",
+        include_str!("../definitions/ardupilotmega.xml"),
+    ),
+];
+
+pub struct MessageDef {
+    pub id: u32,
+    pub name: String,
+    pub description: String,
+    /// Definition file the message comes from
+    pub file: &'static str,
+    pub fields: Vec<FieldDef>,
+}
+
+pub struct FieldDef {
+    /// Name as in the XML; rust-mavlink renames `type` to `mavtype`
+    pub name: String,
+    pub ty: String,
+    pub units: Option<String>,
+    pub enum_name: Option<String>,
+    pub bitmask: bool,
+    pub description: String,
+    /// MAVLink 2 extension field, which mavlink 0.13 does not decode
+    pub extension: bool,
+}
+
+pub struct EnumDef {
+    pub description: String,
+    pub bitmask: bool,
+    pub entries: Vec<EnumEntry>,
+}
+
+pub struct EnumEntry {
+    pub name: String,
+    pub value: u64,
+    pub description: String,
+}
+
+impl EnumDef {
+    pub fn entry(&self, value: u64) -> Option<&EnumEntry> {
+        self.entries.iter().find(|e| e.value == value)
+    }
+}
+
+impl FieldDef {
+    /// The field name used by rust-mavlink, and so by the field tree.
+    pub fn rust_name(&self) -> &str {
+        if self.name == "type" {
+            "mavtype"
+        } else {
+            &self.name
+        }
+    }
+}
+
+struct Dialect {
+    /// Ordered by id
+    messages: Vec<MessageDef>,
+    by_name: HashMap<String, usize>,
+    enums: HashMap<String, EnumDef>,
+    /// XML enum names by the Rust type name rust-mavlink gives them
+    by_type: HashMap<String, String>,
+}
+
+fn dialect() -> &'static Dialect {
+    static DIALECT: OnceLock<Dialect> = OnceLock::new();
+    DIALECT.get_or_init(|| {
+        let mut by_id = BTreeMap::new();
+        let mut enums = HashMap::new();
+        for (file, xml) in DEFINITIONS {
+            if let Err(e) = parse(file, xml, &mut by_id, &mut enums) {
+                log::warn!("Failed to parse {}: {}", file, e);
+            }
+        }
+        let messages: Vec<MessageDef> = by_id.into_values().collect();
+        let by_name = messages
+            .iter()
+            .enumerate()
+            .map(|(i, m)| (m.name.clone(), i))
+            .collect();
+        let by_type = enums
+            .keys()
+            .map(|name| (type_name(name), name.clone()))
+            .collect();
+        Dialect {
+            messages,
+            by_name,
+            enums,
+            by_type,
+        }
+    })
+}
+
+/// Every message of the compiled dialect, ordered by id.
+pub fn messages() -> &'static [MessageDef] {
+    &dialect().messages
+}
+
+pub fn find(name: &str) -> Option<&'static MessageDef> {
+    let dialect = dialect();
+    dialect.by_name.get(name).map(|&i| &dialect.messages[i])
+}
+
+/// An enum with the entries of every definition file that extends it.
+pub fn find_enum(name: &str) -> Option<&'static EnumDef> {
+    dialect().enums.get(name)
+}
+
+/// The enum rust-mavlink generated as the type `ty`, e.g. `MavModeFlag` for
+/// `MAV_MODE_FLAG`.
+pub fn find_enum_type(ty: &str) -> Option<&'static EnumDef> {
+    let dialect = dialect();
+    dialect
+        .by_type
+        .get(ty)
+        .and_then(|name| dialect.enums.get(name))
+}
+
+/// rust-mavlink's type name for an XML enum name: each `_`-separated word
+/// lowercased with its first letter capitalized.
+fn type_name(xml_name: &str) -> String {
+    xml_name
+        .split('_')
+        .map(|word| {
+            let word = word.to_ascii_lowercase();
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn parse(
+    file: &'static str,
+    xml: &str,
+    defs: &mut BTreeMap<u32, MessageDef>,
+    enums: &mut HashMap<String, EnumDef>,
+) -> Result<(), roxmltree::Error> {
+    let doc = roxmltree::Document::parse(xml)?;
+    let section = |tag| doc.root_element().children().find(|n| n.has_tag_name(tag));
+
+    if let Some(section) = section("enums") {
+        for node in section.children().filter(|n| n.has_tag_name("enum")) {
+            let def = enums
+                .entry(node.attribute("name").unwrap_or_default().to_string())
+                .or_insert_with(|| EnumDef {
+                    description: String::new(),
+                    bitmask: false,
+                    entries: Vec::new(),
+                });
+            def.bitmask |= node.attribute("bitmask") == Some("true");
+            for child in node.children().filter(|n| n.is_element()) {
+                match child.tag_name().name() {
+                    "description" if def.description.is_empty() => def.description = text(child),
+                    "entry" => {
+                        let Some(value) = child.attribute("value").and_then(|v| v.parse().ok())
+                        else {
+                            continue;
+                        };
+                        let description = child
+                            .children()
+                            .find(|n| n.has_tag_name("description"))
+                            .map(text)
+                            .unwrap_or_default();
+                        def.entries.push(EnumEntry {
+                            name: child.attribute("name").unwrap_or_default().to_string(),
+                            value,
+                            description,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    let Some(messages) = section("messages") else {
+        return Ok(());
+    };
+
+    for msg in messages.children().filter(|n| n.has_tag_name("message")) {
+        let Some(id) = msg.attribute("id").and_then(|id| id.parse().ok()) else {
+            continue;
+        };
+
+        let mut description = String::new();
+        let mut fields = Vec::new();
+        let mut extension = false;
+        for child in msg.children().filter(|n| n.is_element()) {
+            match child.tag_name().name() {
+                "description" => description = text(child),
+                "extensions" => extension = true,
+                "field" => fields.push(FieldDef {
+                    name: child.attribute("name").unwrap_or_default().to_string(),
+                    ty: child.attribute("type").unwrap_or_default().to_string(),
+                    units: child.attribute("units").map(str::to_string),
+                    enum_name: child.attribute("enum").map(str::to_string),
+                    bitmask: child.attribute("display") == Some("bitmask"),
+                    description: text(child),
+                    extension,
+                }),
+                _ => {}
+            }
+        }
+
+        defs.insert(
+            id,
+            MessageDef {
+                id,
+                name: msg.attribute("name").unwrap_or_default().to_string(),
+                description,
+                file,
+                fields,
+            },
+        );
+    }
+    Ok(())
+}
+
+/// Element text with the XML line wrapping collapsed.
+fn text(node: roxmltree::Node) -> String {
+    let raw: String = node
+        .descendants()
+        .filter(|n| n.is_text())
+        .filter_map(|n| n.text())
+        .collect();
+    raw.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,224 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::fields::{FieldRow, FieldValue};
+use crate::units;
+
+/// One message as copied or saved from the detail pane.
+#[derive(Serialize)]
+pub struct MessageSnapshot {
+    pub time: DateTime<Local>,
+    pub system_id: u8,
+    pub component_id: u8,
+    pub message_id: u32,
+    pub message_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    pub fields: Value,
+}
+
+/// One row of the live table in a table snapshot.
+#[derive(Serialize)]
+pub struct EntrySnapshot {
+    pub system_id: u8,
+    pub component_id: u8,
+    pub message_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    pub count: u64,
+    pub hz: f64,
+    pub bytes_per_sec: f64,
+    pub total_bytes: u64,
+    /// Seconds since the latest instance arrived
+    pub age_secs: f64,
+    pub fields: Value,
+}
+
+#[derive(Serialize)]
+pub struct ConnectionSnapshot {
+    pub name: String,
+    pub uri: String,
+}
+
+/// The whole live table at one moment, written by the snapshot key.
+#[derive(Serialize)]
+pub struct TableSnapshot {
+    pub time: DateTime<Local>,
+    pub connections: Vec<ConnectionSnapshot>,
+    pub total_messages: u64,
+    pub entries: Vec<EntrySnapshot>,
+}
+
+/// Field values as JSON, the way the detail pane shows them: enums by
+/// variant name, bitmasks by flag names and nested structs as objects.
+pub fn fields_json(fields: &[(&'static str, FieldValue)]) -> Value {
+    let mut object = Map::new();
+    for (name, value) in fields {
+        object.insert(name.to_string(), value_json(value));
+    }
+    Value::Object(object)
+}
+
+fn value_json(value: &FieldValue) -> Value {
+    match value {
+        // Parse the printed number so integers stay integers; NaN becomes null
+        FieldValue::Number { text, .. } => serde_json::from_str(text).unwrap_or(Value::Null),
+        FieldValue::Text { text, .. } => Value::String(text.clone()),
+        FieldValue::Enum { variant, .. } => Value::String(variant.clone()),
+        FieldValue::Flags { bits, names, .. } if names.is_empty() => Value::from(*bits),
+        FieldValue::Flags { names, .. } => Value::String(names.clone()),
+        FieldValue::List(items) => Value::Array(items.iter().map(value_json).collect()),
+        FieldValue::Struct { fields, .. } => fields_json(fields),
+    }
+}
+
+/// The field tree as aligned plain text, humanized when `humanize_as` names
+/// the message.
+pub fn fields_text(rows: &[FieldRow], humanize_as: Option<&str>) -> String {
+    let names: Vec<String> = rows
+        .iter()
+        .map(|row| format!("{}{}", "  ".repeat(row.depth), row.name))
+        .collect();
+    let name_width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+    let type_width = rows.iter().map(|r| r.ty.len()).max().unwrap_or(0);
+    let mut text = String::new();
+    for (row, name) in rows.iter().zip(&names) {
+        let value = units::value_text(humanize_as, row, &row.value);
+        let line = format!(
+            "{:<nw$} {:<tw$} {}",
+            name,
+            row.ty,
+            value,
+            nw = name_width,
+            tw = type_width
+        );
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// Puts `text` on the system clipboard with an OSC 52 escape sequence. The
+/// terminal does the copying, so this also works over SSH; inside tmux it
+/// needs `set-clipboard on`.
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    let mut stdout = std::io::stdout();
+    write!(stdout, "{}", osc52(text))?;
+    stdout.flush()?;
+    Ok(())
+}
+
+fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+/// Writes `text` to `path`, or `json` if the path ends in `.json`.
+pub fn save<T: Serialize>(path: &str, text: &str, json: &T) -> Result<()> {
+    if is_json(path) {
+        return write_json(path, json);
+    }
+    create_new(path)?.write_all(text.as_bytes())?;
+    Ok(())
+}
+
+pub fn write_json<T: Serialize>(path: &str, json: &T) -> Result<()> {
+    let mut writer = BufWriter::new(create_new(path)?);
+    serde_json::to_writer_pretty(&mut writer, json)?;
+    writeln!(writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Creates `path` for writing, refusing to replace a file that is already there.
+pub fn create_new(path: &str) -> Result<File> {
+    match OpenOptions::new().write(true).create_new(true).open(path) {
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            anyhow::bail!("file already exists")
+        }
+        result => Ok(result?),
+    }
+}
+
+pub fn is_json(path: &str) -> bool {
+    path.to_lowercase().ends_with(".json")
+}
+
+/// Swaps a `.json` file name for `.txt` and back, for the save prompt.
+pub fn toggle_extension(path: &mut String) {
+    let name_start = path.rfind('/').map_or(0, |i| i + 1);
+    let stem_len = match path[name_start..].rfind('.') {
+        Some(i) => name_start + i,
+        None => path.len(),
+    };
+    let extension = if is_json(path) { ".txt" } else { ".json" };
+    path.truncate(stem_len);
+    path.push_str(extension);
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from(b[0]) << 16 | u32::from(b[1]) << 8 | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 63] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_matches_rfc_4648() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (input, encoded) in vectors {
+            assert_eq!(base64(input.as_bytes()), encoded, "{:?}", input);
+        }
+        assert_eq!(base64(&[0xfb, 0xff]), "+/8=");
+    }
+
+    #[test]
+    fn osc52_wraps_the_encoded_text() {
+        assert_eq!(osc52("foobar"), "\x1b]52;c;Zm9vYmFy\x07");
+    }
+
+    #[test]
+    fn toggle_extension_swaps_json_and_txt() {
+        let toggled = |path: &str| {
+            let mut path = path.to_string();
+            toggle_extension(&mut path);
+            path
+        };
+        assert_eq!(toggled("msg.json"), "msg.txt");
+        assert_eq!(toggled("msg.txt"), "msg.json");
+        assert_eq!(toggled("MSG.JSON"), "MSG.txt");
+        assert_eq!(toggled("msg"), "msg.json");
+        assert_eq!(toggled("logs.v2/msg"), "logs.v2/msg.json");
+        assert_eq!(toggled("logs/msg.tar.txt"), "logs/msg.tar.json");
+    }
+}
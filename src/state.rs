@@ -0,0 +1,68 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Identifies a live table entry across sessions.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct EntryKey {
+    pub sys_id: u8,
+    pub comp_id: u8,
+    pub message: String,
+}
+
+/// Table preferences kept in `state.json` in the config directory.
+#[derive(Default, Serialize, Deserialize)]
+pub struct SavedState {
+    /// Entries left out of the message table
+    #[serde(default)]
+    pub hidden: BTreeSet<EntryKey>,
+    /// Entries kept at the top of the table regardless of sort mode
+    #[serde(default)]
+    pub pinned: BTreeSet<EntryKey>,
+}
+
+/// `$XDG_CONFIG_HOME/mavshark`, falling back to `~/.config/mavshark`.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("mavshark"))
+}
+
+fn state_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("state.json"))
+}
+
+impl SavedState {
+    /// Loads the saved state; a missing or unreadable file gives the defaults.
+    pub fn load() -> Self {
+        let Some(path) = state_path() else {
+            return Self::default();
+        };
+        let Ok(text) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match serde_json::from_str(&text) {
+            Ok(state) => state,
+            Err(e) => {
+                log::warn!("Ignoring invalid {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = state_path().context("No config directory (HOME is not set)")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+}
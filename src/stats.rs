@@ -0,0 +1,476 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::link::SequenceStats;
+use crate::record::{load_recording, RecordedMessage};
+
+#[derive(Serialize)]
+pub struct StreamStats {
+    /// Connection name, for recordings of several connections
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    pub system_id: u8,
+    pub component_id: u8,
+    pub message_name: String,
+    pub count: u64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub mean_hz: f64,
+    /// Rate over the longest inter-arrival interval
+    pub min_hz: f64,
+    /// Most messages within one whole second
+    pub max_hz: f64,
+    /// Standard deviation of the inter-arrival interval
+    pub jitter_ms: f64,
+    /// Largest inter-arrival interval
+    pub max_gap_ms: f64,
+    pub bytes: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct LinkStats {
+    /// Connection name, for recordings of several connections
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    pub system_id: u8,
+    pub component_id: u8,
+    #[serde(flatten)]
+    pub sequence: SequenceStats,
+    pub loss_percent: f64,
+}
+
+#[derive(Serialize)]
+pub struct TypeStats {
+    pub message_name: String,
+    pub count: u64,
+    pub bytes: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct RecordingStats {
+    pub file: String,
+    pub messages: u64,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub duration_secs: f64,
+    pub streams: Vec<StreamStats>,
+    pub links: Vec<LinkStats>,
+    pub types: Vec<TypeStats>,
+}
+
+#[derive(Default)]
+struct StreamAccumulator {
+    count: u64,
+    first: Option<DateTime<Utc>>,
+    last: Option<DateTime<Utc>>,
+    intervals: IntervalStats,
+    /// Message count per whole second since `first`
+    buckets: Vec<u64>,
+    bytes: Option<u64>,
+}
+
+impl StreamAccumulator {
+    fn add(&mut self, msg: &RecordedMessage) {
+        let ts = msg.timestamp;
+        let first = *self.first.get_or_insert(ts);
+        if let Some(last) = self.last {
+            self.intervals.add(secs_between(last, ts));
+        }
+        self.last = Some(ts);
+
+        let bucket = secs_between(first, ts).max(0.0) as usize;
+        if self.buckets.len() <= bucket {
+            self.buckets.resize(bucket + 1, 0);
+        }
+        self.buckets[bucket] += 1;
+
+        // Bytes are only known if every message in the stream carries a size
+        self.bytes = match (self.count, self.bytes, msg.frame_len) {
+            (0, _, Some(len)) => Some(len as u64),
+            (_, Some(total), Some(len)) => Some(total + len as u64),
+            _ => None,
+        };
+        self.count += 1;
+    }
+
+    fn finish(self, (link, system_id, component_id, message_name): StreamKey) -> StreamStats {
+        let first = self.first.unwrap_or_default();
+        let last = self.last.unwrap_or_default();
+        let span = secs_between(first, last);
+        let intervals = self.intervals.count as f64;
+
+        let mean_hz = if span > 0.0 { intervals / span } else { 0.0 };
+        let min_hz = if self.intervals.max > 0.0 {
+            1.0 / self.intervals.max
+        } else {
+            0.0
+        };
+        let jitter_ms = self.intervals.stddev() * 1000.0;
+
+        // The first and last seconds are usually partial; leave them out when
+        // there are full seconds in between.
+        let full = if self.buckets.len() > 2 {
+            &self.buckets[1..self.buckets.len() - 1]
+        } else {
+            &self.buckets[..]
+        };
+        let max_hz = full.iter().copied().max().unwrap_or(0) as f64;
+
+        StreamStats {
+            link,
+            system_id,
+            component_id,
+            message_name,
+            count: self.count,
+            first_seen: first,
+            last_seen: last,
+            mean_hz,
+            min_hz,
+            max_hz,
+            jitter_ms,
+            max_gap_ms: self.intervals.max * 1000.0,
+            bytes: self.bytes,
+        }
+    }
+}
+
+/// Connection, sender and message name: every connection delivers its own copy
+type StreamKey = (Option<String>, u8, u8, String);
+
+/// Running inter-arrival statistics in seconds (Welford's algorithm, so a
+/// whole session fits in constant space).
+#[derive(Default, Clone)]
+pub struct IntervalStats {
+    pub count: u64,
+    mean: f64,
+    m2: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl IntervalStats {
+    pub fn add(&mut self, interval: f64) {
+        if self.count == 0 {
+            self.min = interval;
+            self.max = interval;
+        } else {
+            self.min = self.min.min(interval);
+            self.max = self.max.max(interval);
+        }
+        self.count += 1;
+        let delta = interval - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (interval - self.mean);
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    pub fn stddev(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            (self.m2 / self.count as f64).sqrt()
+        }
+    }
+}
+
+fn secs_between(from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+    (to - from).num_microseconds().unwrap_or(0) as f64 / 1_000_000.0
+}
+
+fn compute(file: &str, messages: &[RecordedMessage]) -> Result<RecordingStats> {
+    let (Some(start), Some(end)) = (
+        messages.iter().map(|m| m.timestamp).min(),
+        messages.iter().map(|m| m.timestamp).max(),
+    ) else {
+        anyhow::bail!("No messages found in {}", file);
+    };
+
+    let mut streams: BTreeMap<StreamKey, StreamAccumulator> = BTreeMap::new();
+    // Each connection carries its own copy of a sender's sequence numbers
+    let mut links: BTreeMap<(Option<String>, u8, u8), SequenceStats> = BTreeMap::new();
+
+    for msg in messages {
+        let key = (
+            msg.link.clone(),
+            msg.header.system_id,
+            msg.header.component_id,
+            msg.message_name.clone(),
+        );
+        streams.entry(key).or_default().add(msg);
+        links
+            .entry((
+                msg.link.clone(),
+                msg.header.system_id,
+                msg.header.component_id,
+            ))
+            .or_default()
+            .update(msg.header.sequence, msg.timestamp);
+    }
+
+    let streams: Vec<StreamStats> = streams
+        .into_iter()
+        .map(|(key, acc)| acc.finish(key))
+        .collect();
+
+    let mut types: BTreeMap<&str, TypeStats> = BTreeMap::new();
+    for stream in &streams {
+        let entry = types
+            .entry(&stream.message_name)
+            .or_insert_with(|| TypeStats {
+                message_name: stream.message_name.clone(),
+                count: 0,
+                bytes: Some(0),
+            });
+        entry.count += stream.count;
+        entry.bytes = entry.bytes.zip(stream.bytes).map(|(a, b)| a + b);
+    }
+    let mut types: Vec<TypeStats> = types.into_values().collect();
+    types.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(b.count.cmp(&a.count)));
+
+    let links = links
+        .into_iter()
+        .map(|((link, system_id, component_id), sequence)| LinkStats {
+            link,
+            system_id,
+            component_id,
+            loss_percent: sequence.loss_percent(),
+            sequence,
+        })
+        .collect();
+
+    Ok(RecordingStats {
+        file: file.to_string(),
+        messages: messages.len() as u64,
+        start,
+        end,
+        duration_secs: secs_between(start, end),
+        streams,
+        links,
+        types,
+    })
+}
+
+pub fn run_stats(file: &str, json: bool) -> Result<()> {
+    let (messages, _) = load_recording(Path::new(file))?;
+    let stats = compute(file, &messages)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else {
+        print_report(&stats);
+    }
+    Ok(())
+}
+
+#[rustfmt::skip]
+const STREAM_HEADERS: &[&str] = &[
+    "SRC", "MESSAGE", "COUNT", "MEAN HZ", "MIN HZ", "MAX HZ", "JITTER MS", "MAX GAP MS",
+    "BYTES", "FIRST", "LAST",
+];
+#[rustfmt::skip]
+const LINK_HEADERS: &[&str] = &[
+    "SRC", "RECEIVED", "LOST", "DUP", "OUT OF ORDER", "RESETS", "LOSS %",
+];
+
+fn print_report(stats: &RecordingStats) {
+    println!("File:     {}", stats.file);
+    println!(
+        "Duration: {:.3} s ({} .. {})",
+        stats.duration_secs,
+        stats.start.format("%Y-%m-%d %H:%M:%S%.3f"),
+        stats.end.format("%H:%M:%S%.3f")
+    );
+    println!("Messages: {}", stats.messages);
+
+    println!("\nStreams");
+    let rows: Vec<Vec<String>> = stats
+        .streams
+        .iter()
+        .map(|s| {
+            vec![
+                source(s.link.as_deref(), s.system_id, s.component_id),
+                s.message_name.clone(),
+                s.count.to_string(),
+                format!("{:.2}", s.mean_hz),
+                format!("{:.2}", s.min_hz),
+                format!("{:.0}", s.max_hz),
+                format!("{:.1}", s.jitter_ms),
+                format!("{:.1}", s.max_gap_ms),
+                format_bytes(s.bytes),
+                s.first_seen.format("%H:%M:%S%.3f").to_string(),
+                s.last_seen.format("%H:%M:%S%.3f").to_string(),
+            ]
+        })
+        .collect();
+    print_table(STREAM_HEADERS, 2, &rows);
+
+    println!("\nLinks");
+    let rows: Vec<Vec<String>> = stats
+        .links
+        .iter()
+        .map(|l| {
+            vec![
+                source(l.link.as_deref(), l.system_id, l.component_id),
+                l.sequence.received.to_string(),
+                l.sequence.lost.to_string(),
+                l.sequence.duplicated.to_string(),
+                l.sequence.out_of_order.to_string(),
+                l.sequence.resets.to_string(),
+                format!("{:.2}", l.loss_percent),
+            ]
+        })
+        .collect();
+    print_table(LINK_HEADERS, 1, &rows);
+
+    println!("\nBytes per type");
+    let rows: Vec<Vec<String>> = stats
+        .types
+        .iter()
+        .map(|t| {
+            vec![
+                t.message_name.clone(),
+                t.count.to_string(),
+                format_bytes(t.bytes),
+            ]
+        })
+        .collect();
+    print_table(&["MESSAGE", "COUNT", "BYTES"], 1, &rows);
+}
+
+/// `sys:comp`, with the connection when the recording names one.
+fn source(link: Option<&str>, system_id: u8, component_id: u8) -> String {
+    match link {
+        Some(link) => format!("{}:{} via {}", system_id, component_id, link),
+        None => format!("{}:{}", system_id, component_id),
+    }
+}
+
+fn format_bytes(bytes: Option<u64>) -> String {
+    match bytes {
+        Some(b) => b.to_string(),
+        None => "-".to_string(),
+    }
+}
+
+/// Prints rows as aligned columns; the first `left` columns are left-aligned,
+/// the rest (numbers) right-aligned.
+fn print_table(headers: &[&str], left: usize, rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.len());
+        }
+    }
+
+    let format_row = |cells: &mut dyn Iterator<Item = &str>| {
+        cells
+            .enumerate()
+            .map(|(i, cell)| {
+                if i < left {
+                    format!("{:<width$}", cell, width = widths[i])
+                } else {
+                    format!("{:>width$}", cell, width = widths[i])
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    };
+
+    println!("{}", format_row(&mut headers.iter().copied()));
+    for row in rows {
+        println!("{}", format_row(&mut row.iter().map(String::as_str)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::record::RecordedHeader;
+
+    fn message(millis: i64, name: &str, link: Option<&str>, sequence: u8) -> RecordedMessage {
+        RecordedMessage {
+            timestamp: DateTime::UNIX_EPOCH + Duration::milliseconds(millis),
+            header: RecordedHeader {
+                system_id: 1,
+                component_id: 1,
+                sequence,
+            },
+            message_id: 0,
+            message_name: name.to_string(),
+            message: String::new(),
+            frame_len: Some(21),
+            link: link.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn rates_and_gaps() {
+        // 10 Hz for 4 s with one 500 ms gap, and a heartbeat every 2 s
+        let mut messages: Vec<RecordedMessage> = (0..40)
+            .filter(|&n| !(21..25).contains(&n))
+            .map(|n| message(n * 100, "ATTITUDE", None, n as u8))
+            .collect();
+        messages.extend((0..3).map(|n| message(n * 2000, "HEARTBEAT", None, 40 + n as u8)));
+        messages.sort_by_key(|m| m.timestamp);
+        let stats = compute("test", &messages).unwrap();
+
+        let attitude = &stats.streams[0];
+        assert_eq!(attitude.message_name, "ATTITUDE");
+        assert_eq!(attitude.count, 36);
+        assert!((attitude.mean_hz - 35.0 / 3.9).abs() < 1e-9);
+        assert!((attitude.min_hz - 2.0).abs() < 1e-9);
+        assert_eq!(attitude.max_hz, 10.0);
+        assert!((attitude.max_gap_ms - 500.0).abs() < 1e-6);
+        assert_eq!(attitude.bytes, Some(36 * 21));
+
+        // Slower than once a second still has a rate
+        let heartbeat = &stats.streams[1];
+        assert!((heartbeat.mean_hz - 0.5).abs() < 1e-9);
+        assert!((heartbeat.min_hz - 0.5).abs() < 1e-9);
+        assert!((heartbeat.max_gap_ms - 2000.0).abs() < 1e-6);
+        assert_eq!(stats.duration_secs, 4.0);
+    }
+
+    #[test]
+    fn streams_and_links_are_per_connection() {
+        let mut messages = Vec::new();
+        for n in 0..10u8 {
+            messages.push(message(i64::from(n) * 100, "ATTITUDE", Some("radio"), n));
+            // The second connection drops every other message
+            if n % 2 == 0 {
+                messages.push(message(i64::from(n) * 100, "ATTITUDE", Some("wifi"), n));
+            }
+        }
+        let stats = compute("test", &messages).unwrap();
+
+        let counts: Vec<(Option<&str>, u64)> = stats
+            .streams
+            .iter()
+            .map(|s| (s.link.as_deref(), s.count))
+            .collect();
+        assert_eq!(counts, [(Some("radio"), 10), (Some("wifi"), 5)]);
+        assert!((stats.streams[1].min_hz - 5.0).abs() < 1e-9);
+
+        let lost: Vec<(Option<&str>, u64)> = stats
+            .links
+            .iter()
+            .map(|l| (l.link.as_deref(), l.sequence.lost))
+            .collect();
+        assert_eq!(lost, [(Some("radio"), 0), (Some("wifi"), 4)]);
+        assert_eq!(stats.types[0].count, 15);
+    }
+
+    #[test]
+    fn an_empty_recording_is_an_error() {
+        assert!(compute("test", &[]).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+use anyhow::{bail, Result};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::Marker;
+
+pub const THEME_NAMES: &[&str] = &["dark", "light", "high-contrast", "mono"];
+
+/// Styles of the live and replay views, by what they mark rather than by color.
+#[derive(Clone, Copy)]
+pub struct Theme {
+    /// "mavshark" badge at the left of the live title bar
+    pub title: Style,
+    pub replay_title: Style,
+    /// Rest of the title bar
+    pub bar: Style,
+    pub alert_badge: Style,
+    pub paused_badge: Style,
+    /// Table header rows
+    pub header: Style,
+    pub border: Style,
+    /// Border of the focused pane and the filter bar while typing
+    pub focused: Style,
+    pub replay_border: Style,
+    /// Selected table row
+    pub selected: Style,
+    /// Rows of a visual selection in the replay view
+    pub range: Style,
+    /// Secondary text: types, timestamps, help
+    pub dim: Style,
+    /// Field names and labels
+    pub name: Style,
+    /// Tree nodes, live values and bars
+    pub accent: Style,
+    /// Pins, hidden counts and enum names
+    pub marker: Style,
+    pub good: Style,
+    pub warn: Style,
+    pub bad: Style,
+    /// A changed value just now, a little while ago and shortly before it fades
+    pub changed: [Style; 3],
+    pub plot: [Style; 6],
+    /// Plot markers by series, so monochrome plots stay distinguishable
+    pub plot_markers: [Marker; 6],
+}
+
+fn fg(color: Color) -> Style {
+    Style::default().fg(color)
+}
+
+fn on(fg: Color, bg: Color) -> Style {
+    Style::default().fg(fg).bg(bg)
+}
+
+impl Theme {
+    pub fn named(name: &str) -> Result<Self> {
+        Ok(match name.to_lowercase().as_str() {
+            "dark" => Self::dark(),
+            "light" => Self::light(),
+            "high-contrast" => Self::high_contrast(),
+            "mono" | "monochrome" => Self::mono(),
+            _ => bail!(
+                "Unknown theme '{}' (expected {})",
+                name,
+                THEME_NAMES.join(", ")
+            ),
+        })
+    }
+
+    /// The theme given on the command line or in the config file, otherwise
+    /// monochrome if `NO_COLOR` is set, otherwise dark. crossterm drops all
+    /// colors under `NO_COLOR` by itself, so a chosen theme turns that off.
+    pub fn resolve(name: Option<&str>) -> Result<Self> {
+        match name {
+            Some(name) => {
+                let theme = Self::named(name)?;
+                crossterm::style::force_color_output(true);
+                Ok(theme)
+            }
+            None if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) => Ok(Self::mono()),
+            None => Ok(Self::dark()),
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            title: on(Color::Black, Color::Cyan).add_modifier(Modifier::BOLD),
+            replay_title: on(Color::Black, Color::Magenta).add_modifier(Modifier::BOLD),
+            bar: Style::default().bg(Color::DarkGray),
+            alert_badge: on(Color::White, Color::Red).add_modifier(Modifier::BOLD),
+            paused_badge: on(Color::Black, Color::Yellow).add_modifier(Modifier::BOLD),
+            header: fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            border: fg(Color::Cyan),
+            focused: fg(Color::Yellow),
+            replay_border: fg(Color::Magenta),
+            selected: on(Color::White, Color::DarkGray),
+            range: Style::default().bg(Color::Blue),
+            dim: fg(Color::DarkGray),
+            name: fg(Color::Green),
+            accent: fg(Color::Cyan),
+            marker: fg(Color::Magenta),
+            good: fg(Color::Green),
+            warn: fg(Color::Yellow),
+            bad: fg(Color::Red),
+            changed: [
+                on(Color::Black, Color::Yellow),
+                fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                fg(Color::Yellow),
+            ],
+            plot: [
+                fg(Color::Cyan),
+                fg(Color::Yellow),
+                fg(Color::Magenta),
+                fg(Color::Green),
+                fg(Color::Red),
+                fg(Color::Blue),
+            ],
+            plot_markers: [Marker::Braille; 6],
+        }
+    }
+
+    /// Dark text for light backgrounds; yellow and cyan are too faint there.
+    pub fn light() -> Self {
+        let orange = Color::Indexed(130);
+        Self {
+            title: on(Color::White, Color::Blue).add_modifier(Modifier::BOLD),
+            replay_title: on(Color::White, Color::Magenta).add_modifier(Modifier::BOLD),
+            bar: on(Color::Black, Color::Gray),
+            alert_badge: on(Color::White, Color::Red).add_modifier(Modifier::BOLD),
+            paused_badge: on(Color::Black, Color::Yellow).add_modifier(Modifier::BOLD),
+            header: fg(Color::Blue).add_modifier(Modifier::BOLD),
+            border: fg(Color::Blue),
+            focused: fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            replay_border: fg(Color::Magenta),
+            selected: on(Color::Black, Color::Gray),
+            range: on(Color::Black, Color::LightBlue),
+            dim: fg(Color::DarkGray),
+            name: fg(Color::Green),
+            accent: fg(Color::Blue),
+            marker: fg(Color::Magenta),
+            good: fg(Color::Green),
+            warn: fg(orange),
+            bad: fg(Color::Red),
+            changed: [
+                on(Color::Black, Color::Yellow),
+                fg(orange).add_modifier(Modifier::BOLD),
+                fg(orange),
+            ],
+            plot: [
+                fg(Color::Blue),
+                fg(Color::Red),
+                fg(Color::Magenta),
+                fg(Color::Green),
+                fg(orange),
+                fg(Color::Black),
+            ],
+            plot_markers: [Marker::Braille; 6],
+        }
+    }
+
+    /// Bright colors and bold text, for glare on a dark terminal.
+    pub fn high_contrast() -> Self {
+        Self {
+            title: on(Color::Black, Color::White).add_modifier(Modifier::BOLD),
+            replay_title: on(Color::Black, Color::LightMagenta).add_modifier(Modifier::BOLD),
+            bar: on(Color::White, Color::Black),
+            alert_badge: on(Color::White, Color::Red).add_modifier(Modifier::BOLD),
+            paused_badge: on(Color::Black, Color::LightYellow).add_modifier(Modifier::BOLD),
+            header: fg(Color::LightYellow).add_modifier(Modifier::BOLD),
+            border: fg(Color::White),
+            focused: fg(Color::LightYellow).add_modifier(Modifier::BOLD),
+            replay_border: fg(Color::LightMagenta),
+            selected: on(Color::Black, Color::White).add_modifier(Modifier::BOLD),
+            range: on(Color::Black, Color::LightCyan),
+            dim: fg(Color::Gray),
+            name: fg(Color::LightGreen),
+            accent: fg(Color::LightCyan),
+            marker: fg(Color::LightMagenta),
+            good: fg(Color::LightGreen),
+            warn: fg(Color::LightYellow),
+            bad: fg(Color::LightRed).add_modifier(Modifier::BOLD),
+            changed: [
+                on(Color::Black, Color::LightYellow).add_modifier(Modifier::BOLD),
+                fg(Color::LightYellow).add_modifier(Modifier::BOLD),
+                fg(Color::LightYellow),
+            ],
+            plot: [
+                fg(Color::LightCyan),
+                fg(Color::LightYellow),
+                fg(Color::LightMagenta),
+                fg(Color::LightGreen),
+                fg(Color::LightRed),
+                fg(Color::White),
+            ],
+            plot_markers: [Marker::Braille; 6],
+        }
+    }
+
+    /// No colors at all; emphasis through bold, underline and reverse video.
+    pub fn mono() -> Self {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let underlined = Style::default().add_modifier(Modifier::UNDERLINED);
+        let reversed = Style::default().add_modifier(Modifier::REVERSED);
+        Self {
+            title: reversed.add_modifier(Modifier::BOLD),
+            replay_title: reversed.add_modifier(Modifier::BOLD),
+            bar: Style::default(),
+            alert_badge: reversed.add_modifier(Modifier::BOLD),
+            paused_badge: reversed,
+            header: bold.add_modifier(Modifier::UNDERLINED),
+            border: Style::default(),
+            focused: bold,
+            replay_border: Style::default(),
+            selected: reversed,
+            range: underlined,
+            dim: Style::default(),
+            name: Style::default(),
+            accent: Style::default(),
+            marker: bold,
+            good: Style::default(),
+            warn: underlined,
+            bad: bold,
+            changed: [reversed, bold, underlined],
+            plot: [Style::default(); 6],
+            plot_markers: [
+                Marker::Braille,
+                Marker::Dot,
+                Marker::Block,
+                Marker::Bar,
+                Marker::HalfBlock,
+                Marker::Braille,
+            ],
+        }
+    }
+}
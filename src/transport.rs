@@ -0,0 +1,316 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use mavlink::ardupilotmega::MavMessage;
+use mavlink::{MavHeader, MavlinkVersion};
+use serial::SerialPort;
+
+/// How long a read blocks before the listener gets to check whether to stop
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+/// Largest UDP payload
+const MAX_DATAGRAM: usize = 65_536;
+
+/// The receiving half of a connection: the raw bytes as they arrive, so the
+/// listener can frame them itself and count what does not parse.
+pub type ByteSource = Box<dyn Read + Send>;
+
+/// The sending half of a connection, used for heartbeats.
+pub struct Writer {
+    target: Target,
+    sequence: Mutex<u8>,
+}
+
+enum Target {
+    /// Sends to the fixed address of `udpout`/`udpbcast`, or to whoever
+    /// last sent to a `udpin` socket
+    Udp {
+        socket: UdpSocket,
+        dest: Arc<Mutex<Option<SocketAddr>>>,
+    },
+    Stream(Mutex<Box<dyn Write + Send>>),
+    /// Recorded files are read-only
+    None,
+}
+
+impl Writer {
+    fn new(target: Target) -> Arc<Self> {
+        Arc::new(Self {
+            target,
+            sequence: Mutex::new(0),
+        })
+    }
+
+    /// Sends `message` as a MAVLink 2 frame with this connection's next
+    /// sequence number.
+    pub fn send(&self, header: &MavHeader, message: &MavMessage) -> io::Result<usize> {
+        let mut sequence = self.sequence.lock().unwrap();
+        let header = MavHeader {
+            sequence: *sequence,
+            ..*header
+        };
+        *sequence = sequence.wrapping_add(1);
+
+        let mut frame = Vec::new();
+        mavlink::write_versioned_msg(&mut frame, MavlinkVersion::V2, header, message)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        match &self.target {
+            Target::Udp { socket, dest } => match *dest.lock().unwrap() {
+                Some(addr) => socket.send_to(&frame, addr),
+                None => Ok(0),
+            },
+            Target::Stream(stream) => {
+                stream.lock().unwrap().write_all(&frame)?;
+                Ok(frame.len())
+            }
+            Target::None => Ok(0),
+        }
+    }
+}
+
+/// Opens a connection URI the way `mavlink::connect` does: `udpin:`,
+/// `udpout:`, `udpbcast:`, `tcpin:` (waits for one client), `tcpout:`,
+/// `serial:PORT:BAUD` and `file:`.
+pub fn open(uri: &str) -> io::Result<(ByteSource, Arc<Writer>)> {
+    if let Some(address) = uri.strip_prefix("udpin:") {
+        let socket = UdpSocket::bind(socket_addr(address)?)?;
+        udp(socket, None, true)
+    } else if let Some(address) = uri.strip_prefix("udpout:") {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        udp(socket, Some(socket_addr(address)?), false)
+    } else if let Some(address) = uri.strip_prefix("udpbcast:") {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.set_broadcast(true)?;
+        udp(socket, Some(socket_addr(address)?), false)
+    } else if let Some(address) = uri.strip_prefix("tcpout:") {
+        tcp(TcpStream::connect(socket_addr(address)?)?)
+    } else if let Some(address) = uri.strip_prefix("tcpin:") {
+        let listener = TcpListener::bind(socket_addr(address)?)?;
+        let (stream, _) = listener.accept()?;
+        tcp(stream)
+    } else if let Some(settings) = uri.strip_prefix("serial:") {
+        serial_port(settings)
+    } else if let Some(path) = uri.strip_prefix("file:") {
+        Ok((Box::new(File::open(path)?), Writer::new(Target::None)))
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::AddrNotAvailable,
+            "Protocol unsupported",
+        ))
+    }
+}
+
+fn socket_addr(address: &str) -> io::Result<SocketAddr> {
+    address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::other("Host address lookup failed"))
+}
+
+fn udp(
+    socket: UdpSocket,
+    dest: Option<SocketAddr>,
+    reply_to_sender: bool,
+) -> io::Result<(ByteSource, Arc<Writer>)> {
+    socket.set_read_timeout(Some(READ_TIMEOUT))?;
+    let dest = Arc::new(Mutex::new(dest));
+    let source = UdpSource {
+        socket: socket.try_clone()?,
+        datagram: vec![0u8; MAX_DATAGRAM].into_boxed_slice(),
+        pending: VecDeque::new(),
+        reply_to: reply_to_sender.then(|| dest.clone()),
+    };
+    Ok((Box::new(source), Writer::new(Target::Udp { socket, dest })))
+}
+
+fn tcp(stream: TcpStream) -> io::Result<(ByteSource, Arc<Writer>)> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let source = Box::new(stream.try_clone()?);
+    Ok((
+        source,
+        Writer::new(Target::Stream(Mutex::new(Box::new(stream)))),
+    ))
+}
+
+fn serial_port(settings: &str) -> io::Result<(ByteSource, Arc<Writer>)> {
+    let invalid = |message| io::Error::new(io::ErrorKind::AddrNotAvailable, message);
+    let (port_name, baud) = settings
+        .rsplit_once(':')
+        .ok_or_else(|| invalid("Incomplete port settings"))?;
+    let baud = baud.parse().map_err(|_| invalid("Invalid baud rate"))?;
+
+    let mut port = serial::open(port_name)?;
+    port.configure(&serial::PortSettings {
+        baud_rate: serial::BaudRate::from_speed(baud),
+        char_size: serial::Bits8,
+        parity: serial::ParityNone,
+        stop_bits: serial::Stop1,
+        flow_control: serial::FlowNone,
+    })?;
+    port.set_timeout(READ_TIMEOUT)?;
+
+    // The port cannot be cloned, so reads and heartbeats take turns
+    let port = SharedPort(Arc::new(Mutex::new(port)));
+    let writer = Writer::new(Target::Stream(Mutex::new(Box::new(port.clone()))));
+    Ok((Box::new(port), writer))
+}
+
+struct UdpSource {
+    socket: UdpSocket,
+    datagram: Box<[u8]>,
+    /// The rest of a datagram larger than the caller's buffer
+    pending: VecDeque<u8>,
+    /// For `udpin`: where to point heartbeats once a peer has sent something
+    reply_to: Option<Arc<Mutex<Option<SocketAddr>>>>,
+}
+
+impl Read for UdpSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.pending.is_empty() {
+            return self.pending.read(buf);
+        }
+        let (len, from) = self.socket.recv_from(&mut self.datagram)?;
+        if let Some(reply_to) = &self.reply_to {
+            *reply_to.lock().unwrap() = Some(from);
+        }
+        let n = len.min(buf.len());
+        buf[..n].copy_from_slice(&self.datagram[..n]);
+        self.pending.extend(&self.datagram[n..len]);
+        // An empty datagram is not the end of the stream
+        if n == 0 {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        Ok(n)
+    }
+}
+
+#[derive(Clone)]
+struct SharedPort(Arc<Mutex<serial::SystemPort>>);
+
+impl Read for SharedPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().read(buf)
+    }
+}
+
+impl Write for SharedPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.lock().unwrap().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use mavlink::ardupilotmega::HEARTBEAT_DATA;
+
+    use super::*;
+    use crate::framing::{FrameEvent, Framer};
+
+    fn free_port() -> u16 {
+        UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+    }
+
+    fn heartbeat(system_id: u8) -> (MavHeader, MavMessage) {
+        let header = MavHeader {
+            system_id,
+            component_id: 1,
+            sequence: 0,
+        };
+        (header, MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()))
+    }
+
+    /// The sender of the next message on `source`.
+    fn receive(source: ByteSource) -> u8 {
+        let mut framer = Framer::new(source);
+        loop {
+            match framer.next_event() {
+                Ok(FrameEvent::Message { header, .. }) => return header.system_id,
+                Err(e)
+                    if e.kind() != io::ErrorKind::WouldBlock
+                        && e.kind() != io::ErrorKind::TimedOut =>
+                {
+                    panic!("{}", e)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_and_incomplete_uris() {
+        let kind = |uri| open(uri).err().map(|e| e.kind());
+        assert_eq!(kind("bluetooth:1"), Some(io::ErrorKind::AddrNotAvailable));
+        assert_eq!(
+            kind("serial:/dev/null"),
+            Some(io::ErrorKind::AddrNotAvailable)
+        );
+        assert_eq!(
+            kind("serial:/dev/null:fast"),
+            Some(io::ErrorKind::AddrNotAvailable)
+        );
+        assert!(open("udpout:not an address").is_err());
+    }
+
+    #[test]
+    fn udp_round_trip() {
+        let port = free_port();
+        let (in_source, in_writer) = open(&format!("udpin:127.0.0.1:{}", port)).unwrap();
+        let (out_source, out_writer) = open(&format!("udpout:127.0.0.1:{}", port)).unwrap();
+
+        let (header, message) = heartbeat(7);
+        out_writer.send(&header, &message).unwrap();
+        assert_eq!(receive(in_source), 7);
+
+        // udpin answers whoever sent to it last
+        let (header, message) = heartbeat(8);
+        assert!(in_writer.send(&header, &message).unwrap() > 0);
+        assert_eq!(receive(out_source), 8);
+    }
+
+    #[test]
+    fn tcp_round_trip() {
+        let port = free_port();
+        let server = thread::spawn(move || open(&format!("tcpin:127.0.0.1:{}", port)));
+        let (client_source, client_writer) = loop {
+            match open(&format!("tcpout:127.0.0.1:{}", port)) {
+                Ok(connection) => break connection,
+                Err(_) => thread::sleep(Duration::from_millis(10)),
+            }
+        };
+        let (server_source, server_writer) = server.join().unwrap().unwrap();
+
+        let (header, message) = heartbeat(3);
+        client_writer.send(&header, &message).unwrap();
+        assert_eq!(receive(server_source), 3);
+        let (header, message) = heartbeat(4);
+        server_writer.send(&header, &message).unwrap();
+        assert_eq!(receive(client_source), 4);
+    }
+
+    #[test]
+    fn reads_recorded_files() {
+        let path = std::env::temp_dir().join(format!("mavshark-{}.tlog", std::process::id()));
+        let (header, message) = heartbeat(5);
+        let mut bytes = Vec::new();
+        mavlink::write_versioned_msg(&mut bytes, MavlinkVersion::V2, header, &message).unwrap();
+        std::fs::write(&path, bytes).unwrap();
+
+        let (source, writer) = open(&format!("file:{}", path.display())).unwrap();
+        assert_eq!(writer.send(&header, &message).unwrap(), 0);
+        assert_eq!(receive(source), 5);
+        std::fs::remove_file(path).unwrap();
+    }
+}
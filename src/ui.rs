@@ -1,15 +1,33 @@
+use std::time::Instant;
+
+use chrono::Local;
 use ratatui::prelude::*;
 use ratatui::widgets::*;
 
-use crate::app::App;
+use crate::app::{self, App, Focus, HistoryView, MessageEntry, Panel, SortMode, TableRow};
+use crate::console;
+use crate::fields::{FieldRow, FieldValue};
+use crate::mouse::HitAreas;
 use crate::replay::ReplayApp;
+use crate::schema;
+use crate::theme::Theme;
+use crate::units;
+use crate::vehicle;
+use crate::watch::Watch;
+
+/// How long a changed field stays highlighted in the detail pane
+const CHANGE_FADE_SECS: f64 = 3.0;
+/// Heartbeat age after which the vehicle panel shows it in red
+const HEARTBEAT_STALE_SECS: f64 = 3.0;
+/// Marker in front of the selected table row
+const TABLE_HIGHLIGHT: &str = " > ";
 
 pub fn draw(f: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(1), // title bar
-            Constraint::Min(0),   // main content
+            Constraint::Min(0),    // main content
             Constraint::Length(3), // filter / help bar
         ])
         .split(f.area());
@@ -20,148 +38,1364 @@ pub fn draw(f: &mut Frame, app: &mut App) {
 }
 
 fn draw_title_bar(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let heartbeat_info = match app.heartbeat {
         Some((sys, comp)) => format!(" | HB: {}:{}", sys, comp),
         None => String::new(),
     };
 
-    let title = Line::from(vec![
-        Span::styled(
-            " mavshark ",
-            Style::default().fg(Color::Black).bg(Color::Cyan).bold(),
-        ),
+    let uris = if app.multi_link() {
+        let names: Vec<&str> = app.connections.iter().map(|c| c.name.as_str()).collect();
+        names.join(" + ")
+    } else {
+        app.connections
+            .first()
+            .map_or(String::new(), |c| c.uri.clone())
+    };
+    let mut spans = vec![
+        Span::styled(" mavshark ", theme.title),
         Span::raw(format!(
             " {} | {} msgs{} | sort: {} ",
-            app.uri, app.total_count, heartbeat_info, app.sort_label()
+            uris,
+            app.total_count,
+            heartbeat_info,
+            app.sort_label()
         )),
-    ]);
+    ];
+    if let Some(loss) = app.total_loss_percent() {
+        spans.push(Span::raw("| "));
+        spans.push(Span::styled(
+            format!("loss: {:.1}% ", loss),
+            loss_style(theme, loss),
+        ));
+    }
+    let hidden = app.hidden_count();
+    if hidden > 0 {
+        spans.push(Span::styled(format!("| {} hidden ", hidden), theme.marker));
+    }
+    let alerts = app.alerts.active_count();
+    if alerts > 0 {
+        spans.push(Span::styled(
+            format!(" {} ALERTS ", alerts),
+            theme.alert_badge,
+        ));
+    }
+    if app.paused_at.is_some() {
+        spans.push(Span::styled(
+            format!(" PAUSED +{} msgs ", app.pending_messages()),
+            theme.paused_badge,
+        ));
+    }
+    let title = Line::from(spans);
 
-    f.render_widget(
-        Paragraph::new(title).style(Style::default().bg(Color::DarkGray)),
-        area,
-    );
+    f.render_widget(Paragraph::new(title).style(theme.bar), area);
 }
 
 fn draw_main(f: &mut Frame, app: &mut App, area: Rect) {
-    let chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
-        .split(area);
+    if app.schema.is_some() {
+        draw_schema_browser(f, app, area);
+        return;
+    }
+
+    let area = match app.panel {
+        Some(panel) => {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Percentage(30)])
+                .split(area);
+            match panel {
+                Panel::Links => draw_links_panel(f, app, rows[1]),
+                Panel::Throughput => draw_throughput_panel(f, app, rows[1]),
+                Panel::Vehicles => draw_vehicles_panel(f, app, rows[1]),
+                Panel::Console => draw_console_panel(f, app, rows[1]),
+                Panel::Alerts => draw_alerts_panel(f, app, rows[1]),
+                Panel::Watch => draw_watch_panel(f, app, rows[1]),
+            }
+            rows[0]
+        }
+        None => area,
+    };
+
+    let area = if app.pinned.is_empty() {
+        area
+    } else {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Percentage(35)])
+            .split(area);
+        draw_plots(f, app, rows[1]);
+        rows[0]
+    };
+
+    let chunks = split_main(area, app.split.percent);
+    app.hit = HitAreas {
+        main: area,
+        table: chunks[0],
+        detail: chunks[1],
+    };
 
     draw_message_list(f, app, chunks[0]);
     draw_message_detail(f, app, chunks[1]);
 }
 
+/// Splits the main area into the message table and the detail pane.
+fn split_main(area: Rect, table_percent: u16) -> std::rc::Rc<[Rect]> {
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(table_percent),
+            Constraint::Percentage(100 - table_percent),
+        ])
+        .split(area)
+}
+
 fn draw_message_list(f: &mut Frame, app: &mut App, area: Rect) {
-    let header = Row::new(vec![
-        Cell::from("Message"),
-        Cell::from("Src"),
-        Cell::from("Hz"),
-        Cell::from("Count"),
-    ])
-    .style(Style::default().bold().fg(Color::Yellow))
+    let theme = app.theme;
+    let multi_link = app.multi_link();
+    // The Link column only shows with several connections
+    let with_link = |mut cells: Vec<Cell<'static>>, link: String| {
+        if multi_link {
+            cells.insert(1, Cell::from(link));
+        }
+        cells
+    };
+    let header = Row::new(with_link(
+        vec![
+            Cell::from("Message"),
+            Cell::from("Src"),
+            Cell::from("Hz"),
+            Cell::from("Count"),
+        ],
+        "Link".to_string(),
+    ))
+    .style(theme.header)
     .bottom_margin(1);
 
+    let node_style = theme.accent.bold();
     let rows: Vec<Row> = app
-        .filtered_indices
+        .rows
+        .iter()
+        .map(|row| match *row {
+            TableRow::System { sys_id, hz, count } => {
+                let arrow = tree_arrow(app, (sys_id, None));
+                Row::new(with_link(
+                    vec![
+                        Cell::from(format!("{} System {}", arrow, sys_id)),
+                        Cell::from(sys_id.to_string()),
+                        Cell::from(format!("{:.1}", hz)),
+                        Cell::from(format_count(count)),
+                    ],
+                    String::new(),
+                ))
+                .style(node_style)
+            }
+            TableRow::Component {
+                sys_id,
+                comp_id,
+                hz,
+                count,
+            } => {
+                let arrow = tree_arrow(app, (sys_id, Some(comp_id)));
+                Row::new(with_link(
+                    vec![
+                        Cell::from(format!("  {} {}", arrow, vehicle::component_name(comp_id))),
+                        Cell::from(format!("{}:{}", sys_id, comp_id)),
+                        Cell::from(format!("{:.1}", hz)),
+                        Cell::from(format_count(count)),
+                    ],
+                    String::new(),
+                ))
+                .style(theme.accent)
+            }
+            TableRow::Message(idx) => {
+                let entry = &app.entries[idx];
+                let indent = if app.tree_view { "    " } else { "" };
+                let mut name = vec![Span::raw(indent)];
+                if entry.pinned_top {
+                    name.push(Span::styled("* ", theme.marker));
+                }
+                name.push(Span::raw(entry.name.clone()));
+                let row = Row::new(with_link(
+                    vec![
+                        Cell::from(Line::from(name)),
+                        Cell::from(format!("{}:{}", entry.sys_id, entry.comp_id)),
+                        Cell::from(format!("{:.1}", entry.hz)),
+                        Cell::from(format_count(entry.count)),
+                    ],
+                    entry.via.clone(),
+                ));
+                if app.alerts.is_active(idx) {
+                    row.style(theme.bad.bold())
+                } else {
+                    row
+                }
+            }
+        })
+        .collect();
+
+    let mut widths = vec![
+        Constraint::Min(20),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(7),
+    ];
+    if multi_link {
+        widths.insert(1, Constraint::Length(app::LINK_COLUMN_WIDTH));
+    }
+    let selection_width = if app.rows.is_empty() || app.table_state.selected().is_none() {
+        0
+    } else {
+        TABLE_HIGHLIGHT.len() as u16
+    };
+    let cells = header_cells(area, &widths, selection_width);
+    let last = cells.len() - 1;
+    app.sort_columns = vec![
+        (cells[0], SortMode::Alphabetical),
+        (cells[last - 1], SortMode::Hz),
+        (cells[last], SortMode::Count),
+    ];
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" Messages ({}) ", app.filtered_indices.len()))
+                .border_style(theme.border),
+        )
+        .row_highlight_style(theme.selected)
+        .highlight_symbol(TABLE_HIGHLIGHT);
+
+    f.render_stateful_widget(table, area, &mut app.table_state);
+}
+
+/// Header cells of a bordered table in `area`, laid out the way `Table`
+/// lays out its columns, with `selection_width` kept for the highlight symbol.
+fn header_cells(area: Rect, widths: &[Constraint], selection_width: u16) -> Vec<Rect> {
+    let inner = area.inner(Margin::new(1, 1));
+    let [_, columns] =
+        Layout::horizontal([Constraint::Length(selection_width), Constraint::Fill(0)]).areas(inner);
+    Layout::horizontal(widths.iter().copied())
+        .spacing(1)
+        .split(columns)
+        .iter()
+        .map(|cell| Rect { height: 1, ..*cell })
+        .collect()
+}
+
+fn draw_schema_browser(f: &mut Frame, app: &mut App, area: Rect) {
+    let Some(browser) = app.schema.as_ref() else {
+        return;
+    };
+    let theme = &app.theme;
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(area);
+
+    let messages = schema::messages();
+    let received = |name: &str| app.entries.iter().any(|e| e.name == name);
+    let items: Vec<ListItem> = browser
+        .matches
+        .iter()
+        .map(|&i| {
+            let def = &messages[i];
+            let mut spans = vec![
+                Span::styled(format!("{:>6} ", def.id), theme.dim),
+                Span::raw(def.name.clone()),
+            ];
+            if received(&def.name) {
+                spans.push(Span::styled(" ●", theme.good));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let mut title = format!(" Dialect ({}/{}) ", browser.matches.len(), messages.len());
+    if browser.search_active || !browser.search.is_empty() {
+        title.push_str(&format!("/{} ", browser.search));
+    }
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .border_style(theme.focused),
+        )
+        .highlight_style(theme.selected)
+        .highlight_symbol(TABLE_HIGHLIGHT);
+    let mut state = ListState::default().with_selected(Some(browser.selected));
+    f.render_stateful_widget(list, chunks[0], &mut state);
+
+    let Some(def) = browser.selected_message() else {
+        f.render_widget(
+            Paragraph::new(" No matching message").block(Block::default().borders(Borders::ALL)),
+            chunks[1],
+        );
+        return;
+    };
+
+    let live: Vec<&MessageEntry> = app.entries.iter().filter(|e| e.name == def.name).collect();
+    let mut lines = vec![Line::from(def.description.clone()), Line::default()];
+    if live.is_empty() {
+        lines.push(Line::styled("Not received", theme.dim));
+    } else {
+        let mut spans = vec![Span::raw("Received from ")];
+        for (i, entry) in live.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw(", "));
+            }
+            spans.push(Span::styled(
+                format!(
+                    "{}:{} ({} msgs, {:.1} Hz)",
+                    entry.sys_id,
+                    entry.comp_id,
+                    format_count(entry.count),
+                    entry.hz
+                ),
+                theme.good,
+            ));
+        }
+        spans.push(Span::styled(" | Enter: show", theme.dim));
+        lines.push(Line::from(spans));
+    }
+    lines.push(Line::default());
+
+    let name_width = def.fields.iter().map(|f| f.name.len()).max().unwrap_or(0);
+    let type_width = def.fields.iter().map(|f| f.ty.len()).max().unwrap_or(0);
+    for field in &def.fields {
+        let mut spans = vec![
+            Span::styled(
+                format!("{:<width$} ", field.name, width = name_width),
+                theme.name,
+            ),
+            Span::styled(
+                format!("{:<width$} ", field.ty, width = type_width),
+                theme.dim,
+            ),
+        ];
+        if let Some(units) = &field.units {
+            spans.push(Span::styled(format!("[{}] ", units), theme.warn));
+        }
+        if let Some(enum_name) = &field.enum_name {
+            let kind = if field.bitmask { "bitmask" } else { "enum" };
+            spans.push(Span::styled(
+                format!("{} {} ", kind, enum_name),
+                theme.marker,
+            ));
+        }
+        if field.extension {
+            spans.push(Span::styled("extension, not decoded ", theme.dim));
+        }
+        let value = live.first().and_then(|entry| {
+            entry
+                .fields
+                .iter()
+                .find(|row| row.path == field.rust_name())
+                .map(|row| row.value.to_string())
+        });
+        if let Some(value) = value {
+            spans.push(Span::styled(format!("= {}", value), theme.accent));
+        }
+        lines.push(Line::from(spans));
+        lines.push(Line::styled(
+            format!("    {}", field.description),
+            theme.dim,
+        ));
+    }
+
+    let detail = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .scroll((browser.scroll as u16, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" {} #{} ({}) ", def.name, def.id, def.file))
+                .border_style(theme.border),
+        );
+    f.render_widget(detail, chunks[1]);
+}
+
+fn tree_arrow(app: &App, node: (u8, Option<u8>)) -> &'static str {
+    if app.collapsed.contains(&node) {
+        "▸"
+    } else {
+        "▾"
+    }
+}
+
+fn draw_message_detail(f: &mut Frame, app: &mut App, area: Rect) {
+    let now = app.view_time();
+    let theme = app.theme;
+    let detail_focused = app.focus == Focus::Detail;
+
+    let title = match app.selected_entry() {
+        Some(entry) if app.humanize => format!(
+            " {} [{}:{}] (humanized) ",
+            entry.name, entry.sys_id, entry.comp_id
+        ),
+        Some(entry) => format!(" {} [{}:{}] ", entry.name, entry.sys_id, entry.comp_id),
+        None => " Detail ".to_string(),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(if detail_focused {
+            theme.focused
+        } else {
+            theme.border
+        });
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let Some(entry) = app.selected_entry() else {
+        f.render_widget(Paragraph::new("No message selected"), inner);
+        return;
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(inner);
+
+    if app.show_histogram {
+        f.render_widget(Paragraph::new(interval_line(&theme, entry)), chunks[0]);
+        draw_interval_histogram(f, &theme, entry, chunks[1]);
+        return;
+    }
+
+    // Keep the field cursor inside the visible rows
+    if detail_focused {
+        let visible = chunks[1].height as usize;
+        let field_count = entry.fields.len();
+        app.field_selected = app.field_selected.min(field_count.saturating_sub(1));
+        if app.field_selected < app.detail_scroll {
+            app.detail_scroll = app.field_selected;
+        } else if visible > 0 && app.field_selected >= app.detail_scroll + visible {
+            app.detail_scroll = app.field_selected + 1 - visible;
+        }
+    }
+
+    let Some(entry) = app.selected_entry() else {
+        return;
+    };
+    let history = if app.history_offset > 0 {
+        entry.history_view(app.history_offset)
+    } else {
+        None
+    };
+    let header = match &history {
+        Some(view) => history_line(&theme, entry, app.history_offset, view),
+        None => interval_line(&theme, entry),
+    };
+    f.render_widget(Paragraph::new(header), chunks[0]);
+
+    // History rows are highlighted as of their own receive time
+    let (rows, now) = match &history {
+        Some(view) => (&view.rows, view.item.received_at),
+        None => (&entry.fields, now),
+    };
+    let humanize_as = app.humanize.then_some(entry.name.as_str());
+    let previous_value = |path: &str| {
+        history
+            .as_ref()
+            .and_then(|view| view.previous.as_ref())
+            .and_then(|(_, prev)| prev.iter().find(|p| p.path == path))
+            .map(|p| &p.value)
+    };
+    let name_width = rows
+        .iter()
+        .map(|row| row.depth * 2 + row.name.len())
+        .max()
+        .unwrap_or(0);
+    let type_width = rows.iter().map(|row| row.ty.len()).max().unwrap_or(0);
+    let lines: Vec<Line> = rows
+        .iter()
+        .enumerate()
+        .skip(app.detail_scroll)
+        .map(|(i, row)| {
+            let mut line = field_line(
+                &theme,
+                row,
+                units::value_text(humanize_as, row, &row.value),
+                app.is_pinned(entry, &row.path),
+                name_width,
+                type_width,
+                now,
+            );
+            if row.changed_at.is_some() && history.is_some() {
+                if let Some(prev) = previous_value(&row.path) {
+                    line.push_span(Span::styled(
+                        format!("  was {}", units::value_text(humanize_as, row, prev)),
+                        theme.dim,
+                    ));
+                }
+            }
+            if detail_focused && i == app.field_selected {
+                line = line.style(Style::default().add_modifier(Modifier::REVERSED));
+            }
+            line
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(lines), chunks[1]);
+}
+
+fn history_line(
+    theme: &Theme,
+    entry: &MessageEntry,
+    offset: usize,
+    view: &HistoryView,
+) -> Line<'static> {
+    let mut text = format!(
+        " history -{}/{} | {}",
+        offset,
+        entry.history.len() - 1,
+        view.item.time.with_timezone(&Local).format("%H:%M:%S%.3f")
+    );
+    if let Some((prev, _)) = &view.previous {
+        let changed = view.rows.iter().filter(|r| r.changed_at.is_some()).count();
+        text.push_str(&format!(
+            " | +{:.1} ms | {} changed",
+            view.item
+                .received_at
+                .duration_since(prev.received_at)
+                .as_secs_f64()
+                * 1000.0,
+            changed
+        ));
+    }
+    Line::styled(text, theme.marker)
+}
+
+fn interval_line(theme: &Theme, entry: &MessageEntry) -> Line<'static> {
+    let stats = &entry.intervals;
+    if stats.count == 0 {
+        return Line::styled(" interval: -", theme.dim);
+    }
+    Line::styled(
+        format!(
+            " interval mean {:.1} ms | σ {:.1} | min {:.1} | max gap {:.1} ms",
+            stats.mean() * 1000.0,
+            stats.stddev() * 1000.0,
+            stats.min * 1000.0,
+            stats.max * 1000.0
+        ),
+        theme.dim,
+    )
+}
+
+const HISTOGRAM_BINS: usize = 15;
+
+fn draw_interval_histogram(f: &mut Frame, theme: &Theme, entry: &MessageEntry, area: Rect) {
+    let samples = &entry.recent_intervals;
+    if samples.is_empty() {
+        f.render_widget(Paragraph::new(" Waiting for a second message"), area);
+        return;
+    }
+
+    let min = samples.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = samples.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let width = ((max - min) / HISTOGRAM_BINS as f64).max(f64::EPSILON);
+    let mut counts = [0u64; HISTOGRAM_BINS];
+    for &sample in samples {
+        let bin = (((sample - min) / width) as usize).min(HISTOGRAM_BINS - 1);
+        counts[bin] += 1;
+    }
+
+    let bars: Vec<Bar> = counts
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| {
+            let start = (min + i as f64 * width) * 1000.0;
+            Bar::default()
+                .value(count)
+                .label(Line::from(format!("{:>8.1} ms", start)))
+                .style(theme.accent)
+        })
+        .collect();
+
+    let chart = BarChart::default()
+        .block(Block::default().title(format!(
+            " Inter-arrival histogram (last {} intervals) ",
+            samples.len()
+        )))
+        .direction(Direction::Horizontal)
+        .bar_width(1)
+        .bar_gap(0)
+        .data(BarGroup::default().bars(&bars));
+    f.render_widget(chart, area);
+}
+
+/// A field's value, humanized when `message` is given and has metadata for it.
+fn field_line(
+    theme: &Theme,
+    row: &FieldRow,
+    value: String,
+    pinned: bool,
+    name_width: usize,
+    type_width: usize,
+    now: Instant,
+) -> Line<'static> {
+    let name = format!("{}{}", "  ".repeat(row.depth), row.name);
+    let marker = if pinned { "* " } else { "  " };
+    Line::from(vec![
+        Span::styled(marker, theme.marker),
+        Span::styled(format!("{:<width$} ", name, width = name_width), theme.name),
+        Span::styled(
+            format!("{:<width$} ", row.ty, width = type_width),
+            theme.dim,
+        ),
+        Span::styled(value, change_style(theme, row.changed_at, now)),
+    ])
+}
+
+/// Highlight for a value that changed recently, fading out over `CHANGE_FADE_SECS`.
+fn change_style(theme: &Theme, changed_at: Option<Instant>, now: Instant) -> Style {
+    let Some(changed_at) = changed_at else {
+        return Style::default();
+    };
+    let age = now.duration_since(changed_at).as_secs_f64();
+    if age < CHANGE_FADE_SECS / 6.0 {
+        theme.changed[0]
+    } else if age < CHANGE_FADE_SECS / 2.0 {
+        theme.changed[1]
+    } else if age < CHANGE_FADE_SECS {
+        theme.changed[2]
+    } else {
+        Style::default()
+    }
+}
+
+fn draw_links_panel(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let multi_link = app.multi_link();
+    let mut header = vec![
+        Cell::from("Src"),
+        Cell::from("Received"),
+        Cell::from("Lost"),
+        Cell::from("Dup"),
+        Cell::from("Out of order"),
+        Cell::from("Loss"),
+    ];
+    if multi_link {
+        header.insert(0, Cell::from("Link"));
+    }
+    let header = Row::new(header).style(theme.header);
+
+    let rows: Vec<Row> = app
+        .links
         .iter()
-        .map(|&idx| {
-            let entry = &app.entries[idx];
+        .map(|(&(link, sys, comp), stats)| {
+            let loss = stats.loss_percent();
+            let mut cells = vec![
+                Cell::from(format!("{}:{}", sys, comp)),
+                Cell::from(format_count(stats.received)),
+                Cell::from(format_count(stats.lost)),
+                Cell::from(format_count(stats.duplicated)),
+                Cell::from(format_count(stats.out_of_order)),
+                Cell::from(format!("{:.2}%", loss)).style(loss_style(theme, loss)),
+            ];
+            if multi_link {
+                let name = app.connections.get(link).map_or("", |c| c.name.as_str());
+                cells.insert(0, Cell::from(name.to_string()));
+            }
+            Row::new(cells)
+        })
+        .collect();
+
+    let mut widths = vec![
+        Constraint::Length(9),
+        Constraint::Length(10),
+        Constraint::Length(8),
+        Constraint::Length(8),
+        Constraint::Length(13),
+        Constraint::Length(8),
+    ];
+    if multi_link {
+        widths.insert(0, Constraint::Length(app::LINK_COLUMN_WIDTH));
+    }
+    let table = Table::new(rows, widths).header(header).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Link quality ")
+            .border_style(theme.border),
+    );
+
+    f.render_widget(table, area);
+}
+
+fn draw_throughput_panel(f: &mut Frame, app: &App, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(area);
+
+    let now = app.view_time();
+    let theme = &app.theme;
+    let health = &app.health;
+    let label = |text: &str| Span::styled(format!("{:<18}", text), theme.name);
+    let error_style = |count: u64| {
+        if count > 0 {
+            theme.bad
+        } else {
+            Style::default()
+        }
+    };
+    let last_good = match health.last_good {
+        Some(t) => format_age(now.duration_since(t).as_secs_f64()),
+        None => "never".to_string(),
+    };
+    let mut lines = vec![
+        Line::from(vec![
+            label("Messages/s"),
+            Span::raw(format!("{:.1}", health.msgs_per_sec)),
+        ]),
+        Line::from(vec![
+            label("Bytes/s"),
+            Span::raw(format_bytes(health.bytes_per_sec)),
+        ]),
+        Line::from(vec![
+            label("Total bytes"),
+            Span::raw(format_bytes(health.total_bytes as f64)),
+        ]),
+        Line::from(vec![
+            label("Discarded bytes"),
+            Span::styled(
+                format_bytes(health.discarded_bytes as f64),
+                error_style(health.discarded_bytes),
+            ),
+        ]),
+        Line::from(vec![
+            label("CRC errors"),
+            Span::styled(
+                health.crc_errors.to_string(),
+                error_style(health.crc_errors),
+            ),
+        ]),
+        Line::from(vec![
+            label("Parse errors"),
+            Span::styled(
+                health.parse_errors.to_string(),
+                error_style(health.parse_errors),
+            ),
+        ]),
+        Line::from(vec![
+            label("I/O errors"),
+            Span::styled(health.io_errors.to_string(), error_style(health.io_errors)),
+        ]),
+        Line::from(vec![label("Last good frame"), Span::raw(last_good)]),
+    ];
+    if let Some((error, at)) = &health.last_error {
+        lines.push(Line::from(vec![
+            label("Last error"),
+            Span::styled(
+                format!(
+                    "{} ago: {}",
+                    format_age(now.duration_since(*at).as_secs_f64()),
+                    error
+                ),
+                theme.bad,
+            ),
+        ]));
+    }
+    if app.multi_link() {
+        for connection in &app.connections {
+            let health = &connection.health;
+            let errors = health.crc_errors + health.parse_errors + health.io_errors;
+            lines.push(Line::from(vec![
+                label(&connection.name),
+                Span::raw(format!(
+                    "{:.1} msg/s  {}/s  ",
+                    health.msgs_per_sec,
+                    format_bytes(health.bytes_per_sec)
+                )),
+                Span::styled(format!("{} err", errors), error_style(errors)),
+            ]));
+        }
+    }
+
+    let summary = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Throughput ")
+            .border_style(theme.border),
+    );
+    f.render_widget(summary, chunks[0]);
+
+    // Bandwidth per message type, summed over all senders
+    let mut by_type: Vec<(&str, f64)> = Vec::new();
+    for entry in &app.entries {
+        match by_type.iter_mut().find(|(name, _)| *name == entry.name) {
+            Some((_, bps)) => *bps += entry.bytes_per_sec,
+            None => by_type.push((&entry.name, entry.bytes_per_sec)),
+        }
+    }
+    by_type.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    let total = health.bytes_per_sec;
+    let rows: Vec<Row> = by_type
+        .iter()
+        .map(|&(name, bps)| {
+            let share = if total > 0.0 { bps / total } else { 0.0 };
             Row::new(vec![
-                Cell::from(entry.name.clone()),
-                Cell::from(format!("{}:{}", entry.sys_id, entry.comp_id)),
-                Cell::from(format!("{:.1}", entry.hz)),
-                Cell::from(format_count(entry.count)),
+                Cell::from(name.to_string()),
+                Cell::from(format_bytes(bps)),
+                Cell::from(format!("{:5.1}%", share * 100.0)),
+                Cell::from("█".repeat((share * 20.0).round() as usize)).style(theme.accent),
             ])
         })
         .collect();
 
+    let header = Row::new(vec![
+        Cell::from("Message"),
+        Cell::from("Bytes/s"),
+        Cell::from("Share"),
+        Cell::from(""),
+    ])
+    .style(theme.header);
+
     let table = Table::new(
         rows,
         [
             Constraint::Min(20),
+            Constraint::Length(10),
             Constraint::Length(7),
-            Constraint::Length(7),
-            Constraint::Length(7),
+            Constraint::Length(20),
         ],
     )
     .header(header)
     .block(
         Block::default()
             .borders(Borders::ALL)
-            .title(format!(" Messages ({}) ", app.filtered_indices.len()))
-            .border_style(Style::default().fg(Color::Cyan)),
+            .title(" Bandwidth by type ")
+            .border_style(theme.border),
+    );
+    f.render_widget(table, chunks[1]);
+}
+
+fn draw_vehicles_panel(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let header = Row::new(vec![
+        Cell::from("Src"),
+        Cell::from("Type"),
+        Cell::from("Autopilot"),
+        Cell::from("Mode"),
+        Cell::from("Armed"),
+        Cell::from("Status"),
+        Cell::from("Battery"),
+        Cell::from("Heartbeat"),
+        Cell::from("Sensors"),
+    ])
+    .style(theme.header);
+
+    let now = app.view_time();
+    let rows: Vec<Row> = app
+        .vehicles
+        .iter()
+        .map(|(&(sys, comp), vehicle)| {
+            let hb = vehicle.heartbeat.as_ref();
+            let text = |value: Option<String>| Cell::from(value.unwrap_or_else(|| "-".to_string()));
+
+            let armed = match vehicle.armed() {
+                Some(true) => Cell::from("ARMED").style(theme.bad.bold()),
+                Some(false) => Cell::from("disarmed").style(theme.good),
+                None => Cell::from("-"),
+            };
+
+            let battery = match vehicle.battery() {
+                (None, None) => "-".to_string(),
+                (voltage, remaining) => {
+                    let voltage = voltage.map_or("-".to_string(), |v| format!("{:.2} V", v));
+                    match remaining {
+                        Some(pct) => format!("{} {}%", voltage, pct),
+                        None => voltage,
+                    }
+                }
+            };
+
+            let heartbeat = match vehicle.last_heartbeat {
+                Some(t) => {
+                    let age = now.duration_since(t).as_secs_f64();
+                    let style = if age > HEARTBEAT_STALE_SECS {
+                        theme.bad
+                    } else {
+                        Style::default()
+                    };
+                    Cell::from(format_age(age)).style(style)
+                }
+                None => Cell::from("-"),
+            };
+
+            let unhealthy = vehicle.unhealthy_sensors();
+            let sensors = if !unhealthy.is_empty() {
+                Cell::from(unhealthy.join(" ")).style(theme.bad)
+            } else {
+                match vehicle.enabled_sensors() {
+                    Some(enabled) => Cell::from(format!("{} OK", enabled)).style(theme.good),
+                    None => Cell::from("-"),
+                }
+            };
+
+            Row::new(vec![
+                Cell::from(format!("{}:{}", sys, comp)),
+                text(hb.map(|hb| vehicle::short_name(hb.mavtype, "MAV_TYPE_"))),
+                text(hb.map(|hb| vehicle::short_name(hb.autopilot, "MAV_AUTOPILOT_"))),
+                text(hb.map(vehicle::flight_mode)),
+                armed,
+                text(hb.map(|hb| vehicle::short_name(hb.system_status, "MAV_STATE_"))),
+                Cell::from(battery),
+                heartbeat,
+                sensors,
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(9),
+            Constraint::Length(14),
+            Constraint::Length(14),
+            Constraint::Length(14),
+            Constraint::Length(9),
+            Constraint::Length(12),
+            Constraint::Length(13),
+            Constraint::Length(10),
+            Constraint::Min(10),
+        ],
     )
-    .row_highlight_style(Style::default().bg(Color::DarkGray).fg(Color::White))
-    .highlight_symbol(" > ");
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Vehicles ")
+            .border_style(theme.border),
+    );
 
-    f.render_stateful_widget(table, area, &mut app.table_state);
+    f.render_widget(table, area);
 }
 
-fn draw_message_detail(f: &mut Frame, app: &App, area: Rect) {
-    let (title, content) = match app.selected_entry() {
-        Some(entry) => {
-            let title = format!(" {} [{}:{}] ", entry.name, entry.sys_id, entry.comp_id);
-            (title, entry.last_content.clone())
-        }
-        None => (" Detail ".to_string(), "No message selected".to_string()),
-    };
+fn draw_console_panel(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let console = &app.console;
+    let visible = console.visible();
+    let height = area.height.saturating_sub(2) as usize;
+    let end = visible.len().saturating_sub(console.scroll);
+    let start = end.saturating_sub(height);
 
-    let lines: Vec<Line> = content
-        .lines()
-        .skip(app.detail_scroll)
+    let lines: Vec<Line> = visible[start..end]
+        .iter()
         .map(|line| {
-            if let Some(colon_pos) = line.find(':') {
-                let (key, val) = line.split_at(colon_pos);
-                Line::from(vec![
-                    Span::styled(key.to_string(), Style::default().fg(Color::Green)),
-                    Span::raw(val.to_string()),
-                ])
+            let severity = line.severity as u8;
+            Line::from(vec![
+                Span::styled(format!("{} ", line.time.format("%H:%M:%S%.3f")), theme.dim),
+                Span::raw(format!(
+                    "{:>7} ",
+                    format!("{}:{}", line.sys_id, line.comp_id)
+                )),
+                Span::styled(
+                    format!("{:<6} ", console::severity_name(severity)),
+                    severity_style(theme, severity),
+                ),
+                Span::styled(line.text.clone(), severity_style(theme, severity)),
+            ])
+        })
+        .collect();
+
+    let mut title = format!(
+        " Console ({}/{}, <= {}) ",
+        visible.len(),
+        console.lines.len(),
+        console::severity_name(console.max_severity)
+    );
+    if !console.search.is_empty() {
+        title.push_str(&format!("/{} ", console.search));
+    }
+    if console.scroll > 0 {
+        title.push_str(&format!("[{} newer] ", console.scroll));
+    }
+    let border_style = if app.focus == Focus::Console {
+        theme.focused
+    } else {
+        theme.border
+    };
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(border_style),
+    );
+    f.render_widget(paragraph, area);
+}
+
+fn draw_alerts_panel(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let alerts = &app.alerts;
+    let height = area.height.saturating_sub(2) as usize;
+    let start = alerts.log.len().saturating_sub(height);
+
+    let lines: Vec<Line> = alerts
+        .log
+        .range(start..)
+        .map(|event| {
+            let (label, style) = if event.triggered {
+                ("ALERT  ", theme.bad.bold())
             } else {
-                Line::from(Span::styled(
-                    line.to_string(),
-                    Style::default().fg(Color::DarkGray),
-                ))
-            }
+                ("CLEARED", theme.good)
+            };
+            Line::from(vec![
+                Span::styled(format!("{} ", event.time.format("%H:%M:%S%.3f")), theme.dim),
+                Span::styled(format!("{} ", label), style),
+                Span::raw(format!(
+                    "{:>7} {:<24} ",
+                    format!("{}:{}", event.sys_id, event.comp_id),
+                    event.message
+                )),
+                Span::styled(event.rule.clone(), theme.warn),
+            ])
         })
         .collect();
 
-    let detail = Paragraph::new(lines).block(
+    let title = if alerts.rules.is_empty() {
+        " Alerts (no rules; see --alert) ".to_string()
+    } else {
+        format!(
+            " Alerts ({} rules, {} active) ",
+            alerts.rules.len(),
+            alerts.active_count()
+        )
+    };
+    let paragraph = Paragraph::new(lines).block(
         Block::default()
             .borders(Borders::ALL)
             .title(title)
-            .border_style(Style::default().fg(Color::Cyan)),
+            .border_style(theme.border),
     );
+    f.render_widget(paragraph, area);
+}
 
-    f.render_widget(detail, area);
+fn draw_watch_panel(f: &mut Frame, app: &App, area: Rect) {
+    let now = app.view_time();
+    let theme = &app.theme;
+    let focused = app.focus == Focus::Watch;
+    let header = Row::new(vec!["Watch", "Value", "Src", "Age", "Min", "Max"]).style(theme.header);
+
+    let show = |watch: &Watch, value: &FieldValue| {
+        app.humanize
+            .then(|| units::humanize_path(&watch.message, &watch.path, value))
+            .flatten()
+            .unwrap_or_else(|| value.to_string())
+    };
+    let number = |watch: &Watch, value: Option<f64>| match value {
+        Some(value) => show(
+            watch,
+            &FieldValue::Number {
+                ty: "f64",
+                value,
+                text: if value.fract() == 0.0 {
+                    value.to_string()
+                } else {
+                    format!("{:.4}", value)
+                },
+            },
+        ),
+        None => "-".to_string(),
+    };
+    // A watch without a sender gets a row per sender, labelled on the first
+    let rows: Vec<Row> = app
+        .watches
+        .iter()
+        .enumerate()
+        .flat_map(|(i, watch)| {
+            let style = if focused && i == app.watch_selected {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else if watch.readings.is_empty() {
+                theme.dim
+            } else {
+                Style::default()
+            };
+            let cells: Vec<[String; 5]> = if watch.readings.is_empty() {
+                vec![["waiting", "-", "-", "-", "-"].map(String::from)]
+            } else {
+                watch
+                    .readings
+                    .iter()
+                    .map(|(&(sys, comp), reading)| {
+                        [
+                            show(watch, &reading.value),
+                            format!("{}:{}", sys, comp),
+                            format_age(now.saturating_duration_since(reading.at).as_secs_f64()),
+                            number(watch, reading.min),
+                            number(watch, reading.max),
+                        ]
+                    })
+                    .collect()
+            };
+            cells
+                .into_iter()
+                .enumerate()
+                .map(move |(n, [value, source, age, min, max])| {
+                    let label = if n == 0 { watch.label() } else { String::new() };
+                    Row::new(vec![
+                        Cell::from(label),
+                        Cell::from(value),
+                        Cell::from(source),
+                        Cell::from(age),
+                        Cell::from(min),
+                        Cell::from(max),
+                    ])
+                    .style(style)
+                })
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(32),
+            Constraint::Min(16),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(14),
+            Constraint::Length(14),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(if app.watches.is_empty() {
+                " Watch (w to add a field) ".to_string()
+            } else {
+                format!(" Watch ({}) ", app.watches.len())
+            })
+            .border_style(if focused { theme.focused } else { theme.border }),
+    );
+    f.render_widget(table, area);
 }
 
-fn draw_filter_bar(f: &mut Frame, app: &App, area: Rect) {
-    let (style, border_style) = if app.filter_active {
-        (
-            Style::default().fg(Color::Yellow),
-            Style::default().fg(Color::Yellow),
-        )
+fn severity_style(theme: &Theme, severity: u8) -> Style {
+    match severity {
+        0..=2 => theme.bad.bold(),
+        3 => theme.bad,
+        4 => theme.warn,
+        5 => theme.accent,
+        6 => Style::default(),
+        _ => theme.dim,
+    }
+}
+
+fn format_bytes(bytes: f64) -> String {
+    if bytes >= 1_000_000.0 {
+        format!("{:.1} MB", bytes / 1_000_000.0)
+    } else if bytes >= 1_000.0 {
+        format!("{:.1} kB", bytes / 1_000.0)
+    } else {
+        format!("{:.0} B", bytes)
+    }
+}
+
+fn format_age(secs: f64) -> String {
+    if secs < 60.0 {
+        format!("{:.1}s", secs)
+    } else {
+        format!("{:.0}m{:02.0}s", (secs / 60.0).floor(), secs % 60.0)
+    }
+}
+
+fn loss_style(theme: &Theme, loss: f64) -> Style {
+    if loss >= 5.0 {
+        theme.bad
+    } else if loss >= 1.0 {
+        theme.warn
     } else {
-        (
-            Style::default().fg(Color::DarkGray),
-            Style::default().fg(Color::DarkGray),
+        theme.good
+    }
+}
+
+fn draw_plots(f: &mut Frame, app: &App, area: Rect) {
+    let now = app.view_time();
+    let theme = &app.theme;
+    let window = app.plot_window_secs;
+
+    // Samples as (seconds relative to now, value), so the x axis runs -window..0
+    let series: Vec<Vec<(f64, f64)>> = app
+        .pinned
+        .iter()
+        .map(|pin| {
+            pin.samples
+                .iter()
+                .map(|&(t, v)| (-now.duration_since(t).as_secs_f64(), v))
+                .collect()
+        })
+        .collect();
+
+    let title = format!(
+        " Plot (last {:.0}s, {} axes) ",
+        window,
+        if app.plot_shared_axes {
+            "shared"
+        } else {
+            "separate"
+        }
+    );
+
+    if app.plot_shared_axes {
+        let datasets = app
+            .pinned
+            .iter()
+            .zip(&series)
+            .enumerate()
+            .map(|(i, (pin, data))| plot_dataset(theme, pin.label(), data, i))
+            .collect();
+        let all = series.iter().flatten();
+        f.render_widget(plot_chart(theme, datasets, all, window, title), area);
+    } else {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Ratio(1, app.pinned.len() as u32);
+                app.pinned.len()
+            ])
+            .split(area);
+        for (i, (pin, data)) in app.pinned.iter().zip(&series).enumerate() {
+            let title = if i == 0 { title.clone() } else { String::new() };
+            let chart = plot_chart(
+                theme,
+                vec![plot_dataset(theme, pin.label(), data, i)],
+                data.iter(),
+                window,
+                title,
+            );
+            f.render_widget(chart, rows[i]);
+        }
+    }
+}
+
+fn plot_dataset<'a>(theme: &Theme, label: String, data: &'a [(f64, f64)], i: usize) -> Dataset<'a> {
+    Dataset::default()
+        .name(label)
+        .marker(theme.plot_markers[i % theme.plot_markers.len()])
+        .graph_type(GraphType::Line)
+        .style(theme.plot[i % theme.plot.len()])
+        .data(data)
+}
+
+fn plot_chart<'a>(
+    theme: &Theme,
+    datasets: Vec<Dataset<'a>>,
+    points: impl Iterator<Item = &'a (f64, f64)>,
+    window: f64,
+    title: String,
+) -> Chart<'a> {
+    let (min, max) = points.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &(_, v)| {
+        (lo.min(v), hi.max(v))
+    });
+    let (min, max) = if min > max {
+        (0.0, 1.0)
+    } else if min == max {
+        (min - 1.0, max + 1.0)
+    } else {
+        let pad = (max - min) * 0.05;
+        (min - pad, max + pad)
+    };
+
+    Chart::new(datasets)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .border_style(theme.border),
+        )
+        .x_axis(
+            Axis::default()
+                .bounds([-window, 0.0])
+                .labels([format!("-{:.0}s", window), "now".to_string()])
+                .style(theme.dim),
         )
+        .y_axis(
+            Axis::default()
+                .bounds([min, max])
+                .labels([format_axis(min), format_axis(max)])
+                .style(theme.dim),
+        )
+        .legend_position(Some(LegendPosition::TopLeft))
+        .hidden_legend_constraints((Constraint::Percentage(90), Constraint::Percentage(90)))
+}
+
+fn format_axis(value: f64) -> String {
+    if value.abs() >= 1000.0 || value.fract() == 0.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+fn draw_filter_bar(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let style = if app.filter_active
+        || app.watch_input.is_some()
+        || app.save_prompt.is_some()
+        || app.console.search_active
+        || app.schema.as_ref().is_some_and(|b| b.search_active)
+    {
+        theme.focused
+    } else {
+        theme.dim
     };
 
-    let filter_text = if app.filter_active {
+    let filter_text = if let Some(prompt) = &app.save_prompt {
+        format!(" save as (Tab json/text): {}_", prompt.path)
+    } else if let Some(status) = &app.status {
+        format!(" {}", status)
+    } else if app.filter_active {
         format!(" / {}_", app.filter)
+    } else if let Some(input) = &app.watch_input {
+        format!(" watch [sys:comp] MESSAGE.field: {}_", input)
+    } else if let Some(browser) = &app.schema {
+        if browser.search_active {
+            format!(" dialect / {}_", browser.search)
+        } else {
+            " j/k navigate | / search name or id | Enter show received message | d/u scroll | Esc/D close"
+                .to_string()
+        }
+    } else if app.console.search_active {
+        format!(" console / {}_", app.console.search)
+    } else if app.focus == Focus::Console {
+        " j/k scroll | G newest | 0-7 max severity | / search | Tab/Esc back to table | c close"
+            .to_string()
+    } else if app.focus == Focus::Watch {
+        " j/k select | w add | x remove | R reset min/max | Tab/Esc back to table | W close"
+            .to_string()
+    } else if app.focus == Focus::Detail {
+        " j/k select field | p pin/unpin plot | w watch | [/] history | y copy | S save | d/u scroll | Tab/Esc back to table"
+            .to_string()
     } else if app.filter.is_empty() {
-        " / search | j/k navigate | s sort | d/u scroll detail | q quit".to_string()
+        " / search | j/k navigate | Tab fields | s sort | T tree | D dialect | p pin top | x hide | X unhide all | Space pause | l links | t throughput | v vehicles | c console | A alerts | w/W watch | h histogram | r raw/humanized | [/] history | a plot axes | P unpin all | y copy | S save | E snapshot | d/u scroll detail | q quit".to_string()
     } else {
         format!(" / {} | Esc clear", app.filter)
     };
+    let mut filter_line = Line::from(filter_text);
+    let notice = app.save_prompt.is_some() || app.status.is_some();
+    if !notice && app.watch_input.is_some() {
+        push_filter_error(theme, &mut filter_line, app.watch_error.as_deref());
+    } else if !notice && (app.filter_active || !app.filter.is_empty()) {
+        push_filter_error(theme, &mut filter_line, app.filter_error.as_deref());
+    }
 
-    let block = Block::default().borders(Borders::ALL).border_style(border_style);
-    let paragraph = Paragraph::new(filter_text).style(style).block(block);
+    let block = Block::default().borders(Borders::ALL).border_style(style);
+    let paragraph = Paragraph::new(filter_line).style(style).block(block);
     f.render_widget(paragraph, area);
 }
 
+/// Appends a filter expression parse error to the filter bar.
+fn push_filter_error(theme: &Theme, line: &mut Line, error: Option<&str>) {
+    if let Some(error) = error {
+        line.push_span(Span::styled(format!("  error: {}", error), theme.bad));
+    }
+}
+
 fn format_count(count: u64) -> String {
     if count >= 1_000_000 {
         format!("{:.1}M", count as f64 / 1_000_000.0)
@@ -179,7 +1413,7 @@ pub fn draw_replay(f: &mut Frame, app: &mut ReplayApp) {
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(1), // title bar
-            Constraint::Min(0),   // main content
+            Constraint::Min(0),    // main content
             Constraint::Length(3), // filter / help bar
         ])
         .split(f.area());
@@ -190,53 +1424,67 @@ pub fn draw_replay(f: &mut Frame, app: &mut ReplayApp) {
 }
 
 fn draw_replay_title_bar(f: &mut Frame, app: &ReplayApp, area: Rect) {
+    let theme = &app.theme;
     let position = if app.filtered_indices.is_empty() {
         "0/0".to_string()
     } else {
         format!("{}/{}", app.selected + 1, app.filtered_indices.len())
     };
 
+    let source_info = if app.sources.len() > 1 {
+        format!(" | source: {}", app.source_filter_label())
+    } else {
+        String::new()
+    };
+
     let title = Line::from(vec![
-        Span::styled(
-            " mavshark replay ",
-            Style::default().fg(Color::Black).bg(Color::Magenta).bold(),
-        ),
-        Span::raw(format!(" {} | {} ", app.file_path, position)),
+        Span::styled(" mavshark replay ", theme.replay_title),
+        Span::raw(format!(
+            " {} | {}{} ",
+            app.sources.join(" + "),
+            position,
+            source_info
+        )),
     ]);
 
-    f.render_widget(
-        Paragraph::new(title).style(Style::default().bg(Color::DarkGray)),
-        area,
-    );
+    f.render_widget(Paragraph::new(title).style(theme.bar), area);
 }
 
 fn draw_replay_main(f: &mut Frame, app: &mut ReplayApp, area: Rect) {
-    let chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
-        .split(area);
+    let chunks = split_main(area, app.split.percent);
+    app.hit = HitAreas {
+        main: area,
+        table: chunks[0],
+        detail: chunks[1],
+    };
 
     draw_replay_message_list(f, app, chunks[0]);
     draw_replay_message_detail(f, app, chunks[1]);
 }
 
 fn draw_replay_message_list(f: &mut Frame, app: &mut ReplayApp, area: Rect) {
-    let header = Row::new(vec![
+    let theme = app.theme;
+    let multi_source = app.sources.len() > 1;
+
+    let mut header_cells = vec![
         Cell::from("#"),
         Cell::from("Time"),
         Cell::from("Message"),
         Cell::from("Src"),
-    ])
-    .style(Style::default().bold().fg(Color::Yellow))
-    .bottom_margin(1);
+    ];
+    if multi_source {
+        header_cells.push(Cell::from("Source"));
+    }
+    let header = Row::new(header_cells).style(theme.header).bottom_margin(1);
 
     let rows: Vec<Row> = app
         .filtered_indices
         .iter()
-        .map(|&idx| {
+        .enumerate()
+        .map(|(row_pos, &idx)| {
             let msg = &app.messages[idx];
             let time = msg.timestamp.format("%H:%M:%S%.3f").to_string();
-            Row::new(vec![
+            let mut cells = vec![
                 Cell::from(format!("{}", idx + 1)),
                 Cell::from(time),
                 Cell::from(msg.message_name.clone()),
@@ -244,95 +1492,163 @@ fn draw_replay_message_list(f: &mut Frame, app: &mut ReplayApp, area: Rect) {
                     "{}:{}",
                     msg.header.system_id, msg.header.component_id
                 )),
-            ])
+            ];
+            if multi_source {
+                cells.push(Cell::from(app.source_label(idx).to_string()));
+            }
+            let row = Row::new(cells);
+            if app.in_selection(row_pos) {
+                row.style(theme.range)
+            } else if !app.row_annotations(row_pos).is_empty() {
+                row.style(theme.bad)
+            } else {
+                row
+            }
         })
         .collect();
 
-    let table = Table::new(
-        rows,
-        [
-            Constraint::Length(6),
-            Constraint::Length(13),
-            Constraint::Min(15),
-            Constraint::Length(7),
-        ],
-    )
-    .header(header)
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title(format!(" Messages ({}) ", app.filtered_indices.len()))
-            .border_style(Style::default().fg(Color::Magenta)),
-    )
-    .row_highlight_style(Style::default().bg(Color::DarkGray).fg(Color::White))
-    .highlight_symbol(" > ");
+    let mut widths = vec![
+        Constraint::Length(6),
+        Constraint::Length(13),
+        Constraint::Min(15),
+        Constraint::Length(7),
+    ];
+    if multi_source {
+        widths.push(Constraint::Length(14));
+    }
+
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" Messages ({}) ", app.filtered_indices.len()))
+                .border_style(theme.replay_border),
+        )
+        .row_highlight_style(theme.selected)
+        .highlight_symbol(TABLE_HIGHLIGHT);
 
     f.render_stateful_widget(table, area, &mut app.table_state);
 }
 
 fn draw_replay_message_detail(f: &mut Frame, app: &ReplayApp, area: Rect) {
+    let theme = &app.theme;
     let (title, content) = match app.selected_message() {
         Some(msg) => {
+            let source = if app.sources.len() > 1 {
+                format!(
+                    " ({})",
+                    app.source_label(app.filtered_indices[app.selected])
+                )
+            } else {
+                String::new()
+            };
+            let via = match &msg.link {
+                Some(link) => format!(" via {}", link),
+                None => String::new(),
+            };
             let title = format!(
-                " {} [{}:{}] ",
-                msg.message_name, msg.header.system_id, msg.header.component_id
+                " {} [{}:{}]{}{} ",
+                msg.message_name, msg.header.system_id, msg.header.component_id, via, source
             );
             (title, msg.message.clone())
         }
         None => (" Detail ".to_string(), "No message selected".to_string()),
     };
 
-    let lines: Vec<Line> = content
-        .lines()
-        .skip(app.detail_scroll)
-        .map(|line| {
+    let notes = app.row_annotations(app.selected).iter().map(|note| {
+        Line::from(vec![
+            Span::styled(
+                format!("{} ", note.timestamp.format("%H:%M:%S%.3f")),
+                theme.dim,
+            ),
+            Span::styled(note.annotation.clone(), theme.bad),
+        ])
+    });
+    let lines: Vec<Line> = notes
+        .chain(content.lines().skip(app.detail_scroll).map(|line| {
             if let Some(colon_pos) = line.find(':') {
                 let (key, val) = line.split_at(colon_pos);
                 Line::from(vec![
-                    Span::styled(key.to_string(), Style::default().fg(Color::Green)),
+                    Span::styled(key.to_string(), theme.name),
                     Span::raw(val.to_string()),
                 ])
             } else {
-                Line::from(Span::styled(
-                    line.to_string(),
-                    Style::default().fg(Color::DarkGray),
-                ))
+                Line::from(Span::styled(line.to_string(), theme.dim))
             }
-        })
+        }))
         .collect();
 
     let detail = Paragraph::new(lines).block(
         Block::default()
             .borders(Borders::ALL)
             .title(title)
-            .border_style(Style::default().fg(Color::Magenta)),
+            .border_style(theme.replay_border),
     );
 
     f.render_widget(detail, area);
 }
 
 fn draw_replay_filter_bar(f: &mut Frame, app: &ReplayApp, area: Rect) {
-    let (style, border_style) = if app.filter_active {
-        (
-            Style::default().fg(Color::Yellow),
-            Style::default().fg(Color::Yellow),
-        )
+    let theme = &app.theme;
+    if app.export_active {
+        let scope = if app.export_filtered {
+            "filtered"
+        } else {
+            "all"
+        };
+        let text = format!(
+            " Export {} msgs ({}) to: {}_ | Tab filtered/all | Enter write | Esc cancel",
+            app.export_indices().len(),
+            scope,
+            app.export_path
+        );
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.good);
+        let paragraph = Paragraph::new(text).style(theme.good).block(block);
+        f.render_widget(paragraph, area);
+        return;
+    }
+
+    let style = if app.filter_active || app.save_active {
+        theme.focused
     } else {
-        (
-            Style::default().fg(Color::DarkGray),
-            Style::default().fg(Color::DarkGray),
-        )
+        theme.dim
+    };
+
+    let source_help = if app.sources.len() > 1 {
+        " | f source"
+    } else {
+        ""
     };
 
-    let filter_text = if app.filter_active {
+    let filter_text = if app.save_active {
+        format!(" save as (Tab json/text): {}_", app.save_path)
+    } else if let Some(status) = &app.status {
+        format!(" {}", status)
+    } else if app.filter_active {
         format!(" / {}_", app.filter)
+    } else if app.visual_anchor.is_some() {
+        let (start, end) = app.selection_range();
+        format!(" VISUAL {} rows | w export | v/Esc cancel", end - start + 1)
     } else if app.filter.is_empty() {
-        " / search | j/k navigate | g/G start/end | d/u scroll detail | q quit".to_string()
+        format!(
+            " / search | j/k navigate | g/G start/end{} | v select | w export | y copy | S save | d/u scroll detail | q quit",
+            source_help
+        )
     } else {
         format!(" / {} | Esc clear", app.filter)
     };
+    let mut filter_line = Line::from(filter_text);
+    if app.status.is_none()
+        && !app.save_active
+        && (app.filter_active || (app.visual_anchor.is_none() && !app.filter.is_empty()))
+    {
+        push_filter_error(theme, &mut filter_line, app.filter_error.as_deref());
+    }
 
-    let block = Block::default().borders(Borders::ALL).border_style(border_style);
-    let paragraph = Paragraph::new(filter_text).style(style).block(block);
+    let block = Block::default().borders(Borders::ALL).border_style(style);
+    let paragraph = Paragraph::new(filter_line).style(style).block(block);
     f.render_widget(paragraph, area);
 }
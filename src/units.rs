@@ -0,0 +1,162 @@
+use crate::fields::{FieldRow, FieldValue};
+use crate::schema::{self, EnumDef, FieldDef};
+
+/// Renders a top-level field value for reading rather than debugging:
+/// scaled to base units, enums with their description and bitmasks split
+/// into flag names. `None` when the metadata has nothing to add.
+pub fn humanize(field: &FieldDef, value: &FieldValue) -> Option<String> {
+    match value {
+        // char[N] arrays are NUL-padded strings
+        FieldValue::List(items) if field.ty.starts_with("char[") => {
+            let bytes: Vec<u8> = items
+                .iter()
+                .filter_map(FieldValue::as_f64)
+                .map(|b| b as u8)
+                .take_while(|&b| b != 0)
+                .collect();
+            Some(format!("{:?}", String::from_utf8_lossy(&bytes)))
+        }
+        FieldValue::List(items) => {
+            let items: Vec<String> = items
+                .iter()
+                .map(|item| humanize_scalar(field, item).unwrap_or_else(|| item.to_string()))
+                .collect();
+            Some(format!("[{}]", items.join(", ")))
+        }
+        _ => humanize_scalar(field, value),
+    }
+}
+
+/// Looks up the metadata of a top-level field by message name and field path.
+pub fn humanize_path(message: &str, path: &str, value: &FieldValue) -> Option<String> {
+    let field = schema::find(message)?
+        .fields
+        .iter()
+        .find(|f| f.rust_name().eq_ignore_ascii_case(path))?;
+    humanize(field, value)
+}
+
+/// A field tree value as shown in the detail pane: humanized when
+/// `message` is given and the field is top-level, raw otherwise.
+pub fn value_text(message: Option<&str>, row: &FieldRow, value: &FieldValue) -> String {
+    message
+        .filter(|_| row.depth == 0)
+        .and_then(|message| humanize_path(message, &row.path, value))
+        .unwrap_or_else(|| value.to_string())
+}
+
+fn humanize_scalar(field: &FieldDef, value: &FieldValue) -> Option<String> {
+    if let Some(def) = field.enum_name.as_deref().and_then(schema::find_enum) {
+        let name = field.enum_name.as_deref().unwrap_or_default();
+        return if field.bitmask || def.bitmask {
+            value
+                .as_f64()
+                .map(|bits| flag_names(name, def, bits as u64))
+        } else {
+            enum_name(name, def, value)
+        };
+    }
+
+    let FieldValue::Number { value, text, .. } = value else {
+        return None;
+    };
+    if !value.is_finite() {
+        return None;
+    }
+    let v = *value;
+    let units = field.units.as_deref()?;
+    Some(match units {
+        "degE7" => format!("{:.7}°", v / 1e7),
+        "degE5" => format!("{:.5}°", v / 1e5),
+        "deg" => format!("{}°", text),
+        "cdeg" => format!("{:.2}°", v / 100.0),
+        "cdeg/s" => format!("{:.2}°/s", v / 100.0),
+        "rad" => format!("{:.2}°", v.to_degrees()),
+        "rad/s" => format!("{:.2}°/s", v.to_degrees()),
+        "mrad/s" => format!("{:.2}°/s", (v / 1000.0).to_degrees()),
+        "mV" => format!("{:.3} V", v / 1000.0),
+        "cV" => format!("{:.2} V", v / 100.0),
+        "mA" => format!("{:.3} A", v / 1000.0),
+        "cA" => format!("{:.2} A", v / 100.0),
+        "mm" => format!("{:.3} m", v / 1000.0),
+        "cm" => format!("{:.2} m", v / 100.0),
+        "dm" => format!("{:.1} m", v / 10.0),
+        "dam" => format!("{} m", v * 10.0),
+        "mm/s" => format!("{:.3} m/s", v / 1000.0),
+        "cm/s" => format!("{:.2} m/s", v / 100.0),
+        "dm/s" => format!("{:.1} m/s", v / 10.0),
+        "cdegC" => format!("{:.2} °C", v / 100.0),
+        "degC" => format!("{} °C", text),
+        "d%" => format!("{:.1} %", v / 10.0),
+        "c%" => format!("{:.2} %", v / 100.0),
+        "cs" => format!("{:.2} s", v / 100.0),
+        "ds" => format!("{:.1} s", v / 10.0),
+        "ms" if field.name.starts_with("time_boot") => clock(v / 1e3),
+        "us" if field.name.starts_with("time_boot") => clock(v / 1e6),
+        _ => format!("{} {}", text, units),
+    })
+}
+
+/// Time since boot as h:mm:ss.mmm.
+fn clock(secs: f64) -> String {
+    let millis = (secs * 1000.0).round() as u64;
+    format!(
+        "{}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Entry name without the enum name, e.g. `QUADROTOR` for `MAV_TYPE_QUADROTOR`.
+fn short<'a>(enum_name: &str, entry: &'a str) -> &'a str {
+    entry
+        .strip_prefix(enum_name)
+        .and_then(|rest| rest.strip_prefix('_'))
+        .unwrap_or(entry)
+}
+
+fn enum_name(name: &str, def: &EnumDef, value: &FieldValue) -> Option<String> {
+    let entry = match value {
+        FieldValue::Enum { variant, .. } => def.entries.iter().find(|e| &e.name == variant),
+        FieldValue::Number { value, .. } => def.entry(*value as u64),
+        _ => None,
+    }?;
+    let short = short(name, &entry.name);
+    // The first sentence is enough to say what a value means; "e.g. a GCS"
+    // does not end one
+    let text = &entry.description;
+    let end = text
+        .match_indices(". ")
+        .map(|(i, _)| i)
+        .find(|&i| text[i + 2..].starts_with(|c: char| c.is_uppercase()));
+    let description = match end {
+        Some(end) => &text[..end],
+        None => text.trim_end_matches('.'),
+    };
+    Some(if description.is_empty() {
+        short.to_string()
+    } else {
+        format!("{} ({})", short, description)
+    })
+}
+
+fn flag_names(name: &str, def: &EnumDef, bits: u64) -> String {
+    let mut rest = bits;
+    let mut names = Vec::new();
+    for entry in &def.entries {
+        if entry.value != 0 && bits & entry.value == entry.value && rest & entry.value != 0 {
+            names.push(short(name, &entry.name).to_string());
+            rest &= !entry.value;
+        }
+    }
+    if rest != 0 {
+        names.push(format!("{:#x}", rest));
+    }
+    if names.is_empty() {
+        "none".to_string()
+    } else {
+        names.join(" | ")
+    }
+}
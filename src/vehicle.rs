@@ -0,0 +1,158 @@
+use std::time::Instant;
+
+use mavlink::ardupilotmega::*;
+use num_traits::FromPrimitive;
+
+/// What HEARTBEAT and SYS_STATUS tell us about one system/component.
+#[derive(Default, Clone)]
+pub struct VehicleState {
+    pub heartbeat: Option<HEARTBEAT_DATA>,
+    pub last_heartbeat: Option<Instant>,
+    pub sys_status: Option<SYS_STATUS_DATA>,
+}
+
+impl VehicleState {
+    pub fn update(&mut self, msg: &MavMessage, at: Instant) {
+        match msg {
+            MavMessage::HEARTBEAT(hb) => {
+                self.heartbeat = Some(hb.clone());
+                self.last_heartbeat = Some(at);
+            }
+            MavMessage::SYS_STATUS(status) => self.sys_status = Some(status.clone()),
+            _ => {}
+        }
+    }
+
+    pub fn armed(&self) -> Option<bool> {
+        self.heartbeat.as_ref().map(|hb| {
+            hb.base_mode
+                .contains(MavModeFlag::MAV_MODE_FLAG_SAFETY_ARMED)
+        })
+    }
+
+    /// Battery voltage in volts and remaining charge in percent, where reported.
+    pub fn battery(&self) -> (Option<f64>, Option<i8>) {
+        match &self.sys_status {
+            Some(status) => (
+                (status.voltage_battery != u16::MAX)
+                    .then(|| status.voltage_battery as f64 / 1000.0),
+                (status.battery_remaining >= 0).then_some(status.battery_remaining),
+            ),
+            None => (None, None),
+        }
+    }
+
+    /// Names of sensors that are present and enabled but reported unhealthy.
+    pub fn unhealthy_sensors(&self) -> Vec<String> {
+        let Some(status) = &self.sys_status else {
+            return Vec::new();
+        };
+        let bad = status.onboard_control_sensors_present
+            & status.onboard_control_sensors_enabled
+            & !status.onboard_control_sensors_health;
+        sensor_names(bad)
+    }
+
+    /// Number of sensors that are present and enabled.
+    pub fn enabled_sensors(&self) -> Option<u32> {
+        let status = self.sys_status.as_ref()?;
+        let enabled =
+            status.onboard_control_sensors_present & status.onboard_control_sensors_enabled;
+        Some(enabled.bits().count_ones())
+    }
+}
+
+fn sensor_names(flags: MavSysStatusSensor) -> Vec<String> {
+    (0..32)
+        .map(|bit| MavSysStatusSensor::from_bits_truncate(1 << bit))
+        .filter(|flag| !flag.is_empty() && flags.contains(*flag))
+        .map(|flag| {
+            let name = format!("{:?}", flag);
+            name.strip_prefix("MAV_SYS_STATUS_SENSOR_")
+                .or_else(|| name.strip_prefix("MAV_SYS_STATUS_"))
+                .unwrap_or(&name)
+                .to_string()
+        })
+        .collect()
+}
+
+/// Strips the MAVLink enum prefix from a Debug name, e.g. `MAV_TYPE_QUADROTOR` -> `QUADROTOR`.
+pub fn short_name(value: impl std::fmt::Debug, prefix: &str) -> String {
+    let name = format!("{:?}", value);
+    name.strip_prefix(prefix).unwrap_or(&name).to_string()
+}
+
+/// Name of a component ID from MAV_COMPONENT, e.g. `AUTOPILOT1` for 1.
+pub fn component_name(comp_id: u8) -> String {
+    match MavComponent::from_u8(comp_id) {
+        Some(component) => short_name(component, "MAV_COMP_ID_"),
+        None => format!("component {}", comp_id),
+    }
+}
+
+/// Decodes `custom_mode` into a flight mode name for ArduPilot and PX4;
+/// other autopilots only get the raw number.
+pub fn flight_mode(hb: &HEARTBEAT_DATA) -> String {
+    match hb.autopilot {
+        MavAutopilot::MAV_AUTOPILOT_ARDUPILOTMEGA => ardupilot_mode(hb.mavtype, hb.custom_mode),
+        MavAutopilot::MAV_AUTOPILOT_PX4 => px4_mode(hb.custom_mode),
+        _ => None,
+    }
+    .unwrap_or_else(|| format!("mode {}", hb.custom_mode))
+}
+
+fn ardupilot_mode(mavtype: MavType, mode: u32) -> Option<String> {
+    use MavType::*;
+    match mavtype {
+        MAV_TYPE_QUADROTOR | MAV_TYPE_HEXAROTOR | MAV_TYPE_OCTOROTOR | MAV_TYPE_TRICOPTER
+        | MAV_TYPE_COAXIAL | MAV_TYPE_HELICOPTER | MAV_TYPE_DODECAROTOR | MAV_TYPE_DECAROTOR => {
+            CopterMode::from_u32(mode).map(|m| short_name(m, "COPTER_MODE_"))
+        }
+        MAV_TYPE_FIXED_WING
+        | MAV_TYPE_VTOL_TAILSITTER_DUOROTOR
+        | MAV_TYPE_VTOL_TAILSITTER_QUADROTOR
+        | MAV_TYPE_VTOL_TILTROTOR
+        | MAV_TYPE_VTOL_FIXEDROTOR
+        | MAV_TYPE_VTOL_TAILSITTER
+        | MAV_TYPE_VTOL_TILTWING
+        | MAV_TYPE_VTOL_RESERVED5 => {
+            PlaneMode::from_u32(mode).map(|m| short_name(m, "PLANE_MODE_"))
+        }
+        MAV_TYPE_GROUND_ROVER | MAV_TYPE_SURFACE_BOAT => {
+            RoverMode::from_u32(mode).map(|m| short_name(m, "ROVER_MODE_"))
+        }
+        MAV_TYPE_SUBMARINE => SubMode::from_u32(mode).map(|m| short_name(m, "SUB_MODE_")),
+        MAV_TYPE_ANTENNA_TRACKER => {
+            TrackerMode::from_u32(mode).map(|m| short_name(m, "TRACKER_MODE_"))
+        }
+        _ => None,
+    }
+}
+
+/// PX4 packs its main mode into bits 16..24 and the sub mode into bits 24..32.
+fn px4_mode(custom_mode: u32) -> Option<String> {
+    let main = (custom_mode >> 16) & 0xff;
+    let sub = (custom_mode >> 24) & 0xff;
+    let name = match main {
+        1 => "MANUAL",
+        2 => "ALTCTL",
+        3 => "POSCTL",
+        4 => match sub {
+            1 => "AUTO_READY",
+            2 => "AUTO_TAKEOFF",
+            3 => "AUTO_LOITER",
+            4 => "AUTO_MISSION",
+            5 => "AUTO_RTL",
+            6 => "AUTO_LAND",
+            8 => "AUTO_FOLLOW_TARGET",
+            9 => "AUTO_PRECLAND",
+            _ => "AUTO",
+        },
+        5 => "ACRO",
+        6 => "OFFBOARD",
+        7 => "STABILIZED",
+        8 => "RATTITUDE",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use anyhow::{bail, Result};
+
+use crate::fields::{FieldRow, FieldValue};
+
+/// A `MESSAGE.field` value followed across messages for the watch panel.
+#[derive(Clone)]
+pub struct Watch {
+    /// Only follow this sender; otherwise every sender, each on its own
+    pub source: Option<(u8, u8)>,
+    pub message: String,
+    pub path: String,
+    /// Latest value per sender, so senders of the same message never mix
+    pub readings: BTreeMap<(u8, u8), Reading>,
+}
+
+/// What one sender last sent for a watched field.
+#[derive(Clone)]
+pub struct Reading {
+    pub value: FieldValue,
+    pub at: Instant,
+    /// Range of the values of a number field since the watch was added or reset
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl Watch {
+    /// Parses `MESSAGE.field`, optionally preceded by `sys:comp`, e.g.
+    /// `1:1 ATTITUDE.roll` or `SYS_STATUS.voltage_battery`.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let words: Vec<&str> = spec.split_whitespace().collect();
+        let (source, field) = match words.as_slice() {
+            [field] => (None, *field),
+            [source, field] => {
+                let parsed = source
+                    .split_once(':')
+                    .and_then(|(sys, comp)| Some((sys.parse().ok()?, comp.parse().ok()?)));
+                match parsed {
+                    Some(source) => (Some(source), *field),
+                    None => bail!("Invalid sender '{}' (expected sys:comp)", source),
+                }
+            }
+            _ => bail!("Expected [sys:comp] MESSAGE.field"),
+        };
+        let Some((message, path)) = field
+            .split_once('.')
+            .filter(|(m, p)| !m.is_empty() && !p.is_empty())
+        else {
+            bail!("Invalid watch '{}' (expected MESSAGE.field)", field);
+        };
+        Ok(Self {
+            source,
+            message: message.to_uppercase(),
+            path: path.to_string(),
+            readings: BTreeMap::new(),
+        })
+    }
+
+    pub fn label(&self) -> String {
+        match self.source {
+            Some((sys, comp)) => format!("{}:{} {}.{}", sys, comp, self.message, self.path),
+            None => format!("{}.{}", self.message, self.path),
+        }
+    }
+
+    /// Takes the field from a message if it is the one being watched.
+    pub fn update(&mut self, source: (u8, u8), name: &str, rows: &[FieldRow], at: Instant) {
+        if name != self.message || self.source.is_some_and(|s| s != source) {
+            return;
+        }
+        let Some(row) = rows
+            .iter()
+            .find(|r| r.path.eq_ignore_ascii_case(&self.path))
+        else {
+            return;
+        };
+        let reading = self.readings.entry(source).or_insert(Reading {
+            value: row.value.clone(),
+            at,
+            min: None,
+            max: None,
+        });
+        if let FieldValue::Number { value: v, .. } = row.value {
+            reading.min = Some(reading.min.map_or(v, |min| min.min(v)));
+            reading.max = Some(reading.max.map_or(v, |max| max.max(v)));
+        }
+        reading.value = row.value.clone();
+        reading.at = at;
+    }
+
+    /// Restarts the min/max ranges from the current values.
+    pub fn reset(&mut self) {
+        for reading in self.readings.values_mut() {
+            reading.min = match reading.value {
+                FieldValue::Number { value, .. } => Some(value),
+                _ => None,
+            };
+            reading.max = reading.min;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roll(value: f64) -> Vec<FieldRow> {
+        vec![FieldRow {
+            path: "roll".to_string(),
+            depth: 0,
+            name: "roll".to_string(),
+            ty: "f32".to_string(),
+            value: FieldValue::Number {
+                ty: "f32",
+                value,
+                text: value.to_string(),
+            },
+            changed_at: None,
+        }]
+    }
+
+    /// `sys:comp min..max` per sender
+    fn ranges(watch: &Watch) -> Vec<String> {
+        watch
+            .readings
+            .iter()
+            .map(|((sys, comp), r)| {
+                format!("{}:{} {}..{}", sys, comp, r.min.unwrap(), r.max.unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keeps_senders_apart() {
+        let mut watch = Watch::parse("ATTITUDE.roll").unwrap();
+        let now = Instant::now();
+        watch.update((1, 1), "ATTITUDE", &roll(0.5), now);
+        watch.update((2, 1), "ATTITUDE", &roll(-3.0), now);
+        watch.update((1, 1), "ATTITUDE", &roll(0.7), now);
+        watch.update((1, 1), "GPS_RAW_INT", &roll(9.0), now);
+        assert_eq!(ranges(&watch), ["1:1 0.5..0.7", "2:1 -3..-3"]);
+
+        watch.reset();
+        assert_eq!(ranges(&watch), ["1:1 0.7..0.7", "2:1 -3..-3"]);
+    }
+
+    #[test]
+    fn follows_only_its_sender() {
+        let mut watch = Watch::parse("2:1 attitude.roll").unwrap();
+        watch.update((1, 1), "ATTITUDE", &roll(0.5), Instant::now());
+        assert!(watch.readings.is_empty());
+        watch.update((2, 1), "ATTITUDE", &roll(0.6), Instant::now());
+        assert_eq!(ranges(&watch), ["2:1 0.6..0.6"]);
+    }
+}